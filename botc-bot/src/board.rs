@@ -0,0 +1,144 @@
+use crate::*;
+
+use serenity::http::StatusCode;
+
+// The status board is a single message in the public channel, edited in place
+// like the control panel. It only shows what everyone sitting in the town
+// square already knows, so nothing from the grimoire goes on it, and nobody
+// is shown dead until their death has been announced
+
+pub fn get_board_description(current_state: &BloodGuild) -> String {
+    let mut content = format!("**{}**\n", current_state.get_time_str());
+
+    let alive = current_state
+        .roles
+        .iter()
+        .filter(|p| current_state.is_publicly_alive(p.0))
+        .count();
+
+    let votes = current_state
+        .roles
+        .iter()
+        .filter(|p| {
+            current_state.is_publicly_alive(p.0) || !current_state.used_ghost_votes.contains(&p.0)
+        })
+        .count();
+
+    content.push_str(&format!(
+        "**{}** alive of {}, **{}** votes left\n\n",
+        alive,
+        current_state.roles.len(),
+        votes
+    ));
+
+    for (seat, (user_id, _, character)) in current_state.roles.iter().enumerate() {
+        let life = if current_state.is_publicly_alive(*user_id) {
+            "Alive"
+        } else if current_state.used_ghost_votes.contains(user_id) {
+            "💀 Dead, vote used"
+        } else {
+            "💀 Dead, has ghost vote"
+        };
+
+        // Travelers say who they are when they join, so they're public
+        let traveler = match character {
+            Some(value) if matches!(value.char_type, CharacterType::Traveler) => {
+                format!(" (Traveler: **{}**)", value.name)
+            }
+            _ => String::new(),
+        };
+
+        content.push_str(&format!(
+            "{}) <@{}>{}: {}\n",
+            seat + 1,
+            user_id,
+            traveler,
+            life
+        ));
+    }
+
+    for fabled in &current_state.fabled {
+        content.push_str(&format!(
+            "\n**{}** (Fabled)\n*{}*\n",
+            fabled.name, fabled.ability
+        ));
+    }
+
+    content
+}
+
+async fn post_status_board(ctx: &Context, current_state: &BloodGuild, channel: u64) -> Option<u64> {
+    let description = get_board_description(current_state);
+
+    match ChannelId(channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.title("🩸 Town Square").description(description))
+        })
+        .await
+    {
+        Ok(message) => Some(*message.id.as_u64()),
+        Err(why) => {
+            print_error(&format!("Could not post status board: {:?}", why));
+            None
+        }
+    }
+}
+
+/// Edits the status board in the public channel, posting a new one if there
+/// isn't one yet or the old one was deleted
+pub async fn update_status_board(ctx: &Context, guild_id: u64) {
+    let current_state = match BLOOD_DATABASE.lock().await.blood_guilds.get(&guild_id) {
+        Some(value) => value.clone(),
+        None => return,
+    };
+
+    let channel = match current_state.public_channel {
+        Some(value) => value,
+        None => return,
+    };
+
+    if let Some(message_id) = current_state.status_board {
+        let description = get_board_description(&current_state);
+
+        let result = ChannelId(channel)
+            .edit_message(&ctx.http, message_id, |m| {
+                m.embed(|e| e.title("🩸 Town Square").description(description))
+            })
+            .await;
+
+        match result {
+            Ok(_) => return,
+            // Only a deleted board gets replaced, anything else might work next time
+            Err(serenity::Error::Http(why)) if why.status_code() == Some(StatusCode::NOT_FOUND) => {
+                print_info("Status board was deleted, posting a new one");
+            }
+            Err(why) => {
+                print_error(&format!("Could not update status board: {:?}", why));
+                return;
+            }
+        }
+    }
+
+    let message_id = post_status_board(ctx, &current_state, channel).await;
+
+    if let Some(value) = BLOOD_DATABASE.lock().await.blood_guilds.get_mut(&guild_id) {
+        value.status_board = message_id;
+    }
+}
+
+/// Leaves the status board showing the game is over
+pub async fn close_status_board(ctx: &Context, old_state: &BloodGuild) {
+    let (channel, message_id) = match (old_state.public_channel, old_state.status_board) {
+        (Some(channel), Some(message_id)) => (channel, message_id),
+        _ => return,
+    };
+
+    if let Err(why) = ChannelId(channel)
+        .edit_message(&ctx.http, message_id, |m| {
+            m.embed(|e| e.title("🩸 Town Square").description("**Game over!**"))
+        })
+        .await
+    {
+        print_error(&format!("Could not close status board: {:?}", why));
+    }
+}
@@ -0,0 +1,309 @@
+use crate::*;
+
+// Characters and alignments that change during the game, like a Pit-Hag's
+// target, an Imp's star-pass, a Barber's swap or the Goon turning evil
+
+const CHARACTER_CHANGED: &str = "Your character has changed!";
+const ALIGNMENT_CHANGED: &str = "Your alignment has changed!";
+
+async fn tell_player(
+    ctx: &Context,
+    msg: &impl CommandSource,
+    current_state: &BloodGuild,
+    seat: usize,
+    heading: &str,
+) {
+    // Players only know their role once it's been sent out
+    if !matches!(current_state.game_state, GameState::Playing) {
+        return;
+    }
+
+    let (_, member, character) = &current_state.roles[seat];
+
+    if let Some(character) = character {
+        let mut content = format!(
+            "**{}** {}",
+            heading,
+            get_role_message(current_state, character)
+        );
+
+        // Travelers are already told their alignment in the role message
+        if !matches!(character.char_type, CharacterType::Traveler) {
+            content.push_str(&format!("\nYou are **{}**.", character.alignment.as_str()));
+        }
+
+        send_dm(ctx, msg, member, content, "their role").await;
+    }
+}
+
+// Anyone who wakes differently now needs the new order
+async fn show_new_night_order(ctx: &Context, msg: &impl CommandSource, current_state: &BloodGuild) {
+    if let Time::Night = current_state.time {
+        send_embed(
+            msg,
+            ctx,
+            String::from("Night Order Updated"),
+            current_state.get_night_order_text(),
+            Some(current_state.get_time_str()),
+        )
+        .await;
+    }
+}
+
+async fn save_change(ctx: &Context, msg: &impl CommandSource, current_state: BloodGuild) {
+    let guild_id = current_state.id;
+
+    show_new_night_order(ctx, msg, &current_state).await;
+
+    set_database(current_state).await;
+
+    update_control_panel(ctx, guild_id).await;
+
+    update_status_board(ctx, guild_id).await;
+}
+
+pub async fn become_character(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seat = match params.get(1).and_then(|p| current_state.find_seat(p)) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please give a seat and their new character, like \"become 3 imp\"!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let role = params.get(2..).map(|r| r.join(" ")).unwrap_or_default();
+
+    let mut problems: Vec<String> = Vec::new();
+
+    let mut character = match find_role_entry(&current_state, &role, &mut problems) {
+        Some(value) => value,
+        None => {
+            send_msg(msg, ctx, format!("**Error:** {}", problems.join("\n"))).await;
+            return;
+        }
+    };
+
+    // Without a believed character, the player would be told what they really are
+    if needs_role(&Some(character.clone())) {
+        send_msg(
+            msg,
+            ctx,
+            format!(
+                "**Error:** Please give who the {} believes they are, like \"become 3 {} (chef)\"!",
+                character.name,
+                character.name.to_lowercase()
+            ),
+        )
+        .await;
+        return;
+    }
+
+    // New characters keep the player's alignment, so a star-passed Minion stays evil
+    if let Some(old) = &current_state.roles[seat].2 {
+        character.alignment = old.alignment.clone();
+    }
+
+    let name = get_member_name(&current_state.roles[seat].1);
+    let character_name = character.name.clone();
+
+    let order = current_state.get_night_order();
+
+    current_state.roles[seat].2 = Some(character);
+
+    if current_state.breaks_spirit_of_ivory() {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** The Spirit of Ivory allows only 1 extra evil player!"),
+        )
+        .await;
+        return;
+    }
+
+    print_info(&format!("User {} became the {}", name, character_name));
+    send_msg(
+        msg,
+        ctx,
+        format!("*{}* is now the **{}**", name, character_name),
+    )
+    .await;
+
+    tell_player(ctx, msg, &current_state, seat, CHARACTER_CHANGED).await;
+
+    if let Time::Night = current_state.time {
+        current_state.keep_night_step(&order);
+    }
+
+    save_change(ctx, msg, current_state).await;
+}
+
+pub async fn swap_characters(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seats = (
+        params.get(1).and_then(|p| current_state.find_seat(p)),
+        params.get(2).and_then(|p| current_state.find_seat(p)),
+    );
+
+    let (first, second) = match seats {
+        (Some(first), Some(second)) if first != second => (first, second),
+        _ => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please give two different seats, like \"swap 3 7\"!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    // A Barber swaps only characters, a Snake Charmer swaps alignments too
+    let with_alignments = params.get(3) == Some(&"alignments");
+
+    let mut first_char = current_state.roles[first].2.clone();
+    let mut second_char = current_state.roles[second].2.clone();
+
+    if !with_alignments {
+        if let (Some(a), Some(b)) = (&mut first_char, &mut second_char) {
+            std::mem::swap(&mut a.alignment, &mut b.alignment);
+        }
+    }
+
+    let order = current_state.get_night_order();
+
+    current_state.roles[first].2 = second_char;
+    current_state.roles[second].2 = first_char;
+
+    if current_state.breaks_spirit_of_ivory() {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** The Spirit of Ivory allows only 1 extra evil player!"),
+        )
+        .await;
+        return;
+    }
+
+    let names = (
+        get_member_name(&current_state.roles[first].1),
+        get_member_name(&current_state.roles[second].1),
+    );
+
+    print_info(&format!(
+        "Users {} and {} swapped characters",
+        names.0, names.1
+    ));
+    send_msg(
+        msg,
+        ctx,
+        format!(
+            "*{}* and *{}* swapped characters{}",
+            names.0,
+            names.1,
+            if with_alignments {
+                " and alignments"
+            } else {
+                ""
+            }
+        ),
+    )
+    .await;
+
+    tell_player(ctx, msg, &current_state, first, CHARACTER_CHANGED).await;
+    tell_player(ctx, msg, &current_state, second, CHARACTER_CHANGED).await;
+
+    if let Time::Night = current_state.time {
+        current_state.keep_night_step(&order);
+    }
+
+    save_change(ctx, msg, current_state).await;
+}
+
+pub async fn set_alignment(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seat = params.get(1).and_then(|p| current_state.find_seat(p));
+
+    let alignment = match params.get(2).map(|a| a.to_lowercase()) {
+        Some(value) if value == "good" => Some(Alignment::Good),
+        Some(value) if value == "evil" => Some(Alignment::Evil),
+        _ => None,
+    };
+
+    let (seat, alignment) = match (seat, alignment) {
+        (Some(seat), Some(alignment)) => (seat, alignment),
+        _ => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please give a seat and good or evil, like \"align 3 evil\"!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let name = get_member_name(&current_state.roles[seat].1);
+
+    match &mut current_state.roles[seat].2 {
+        Some(character) => character.alignment = alignment,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                format!("**Error:** *{}* doesn't have a role yet!", name),
+            )
+            .await;
+            return;
+        }
+    }
+
+    if current_state.breaks_spirit_of_ivory() {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** The Spirit of Ivory allows only 1 extra evil player!"),
+        )
+        .await;
+        return;
+    }
+
+    let alignment = current_state.roles[seat]
+        .2
+        .as_ref()
+        .map_or("", |c| c.alignment.as_str())
+        .to_string();
+
+    print_info(&format!("User {} is now {}", name, alignment));
+    send_msg(msg, ctx, format!("*{}* is now **{}**", name, alignment)).await;
+
+    tell_player(ctx, msg, &current_state, seat, ALIGNMENT_CHANGED).await;
+
+    save_change(ctx, msg, current_state).await;
+}
@@ -0,0 +1,518 @@
+use crate::*;
+
+use serenity::builder::CreateComponents;
+use serenity::model::interactions::message_component::MessageComponentInteraction;
+
+// Players whose characters make a choice at night can be DMed a menu to make
+// it, instead of the storyteller asking in every night room. Their answers
+// show up in the night walkthrough
+
+#[derive(Clone, Copy)]
+enum Choice {
+    Players(u64),
+    Character,
+    YesNo,
+}
+
+impl Choice {
+    fn get_prompt(&self) -> String {
+        match self {
+            Choice::Players(1) => String::from("Choose a player"),
+            Choice::Players(count) => format!("Choose {} players", count),
+            Choice::Character => String::from("Choose a character"),
+            Choice::YesNo => String::from("Use your ability tonight?"),
+        }
+    }
+}
+
+const NIGHT_CHOICES: &[(&str, &[Choice])] = &[
+    ("Poisoner", &[Choice::Players(1)]),
+    ("Imp", &[Choice::Players(1)]),
+    ("Butler", &[Choice::Players(1)]),
+    ("Fortune Teller", &[Choice::Players(2)]),
+    ("Monk", &[Choice::Players(1)]),
+    ("Ravenkeeper", &[Choice::Players(1)]),
+    ("Bureaucrat", &[Choice::Players(1)]),
+    ("Thief", &[Choice::Players(1)]),
+    ("Zombuul", &[Choice::Players(1)]),
+    ("Pukka", &[Choice::Players(1)]),
+    ("Shabaloth", &[Choice::Players(2)]),
+    ("Po", &[Choice::Players(1)]),
+    ("Godfather", &[Choice::Players(1)]),
+    ("Devil’s Advocate", &[Choice::Players(1)]),
+    ("Assassin", &[Choice::YesNo, Choice::Players(1)]),
+    ("Sailor", &[Choice::Players(1)]),
+    ("Chambermaid", &[Choice::Players(2)]),
+    ("Exorcist", &[Choice::Players(1)]),
+    ("Innkeeper", &[Choice::Players(2)]),
+    ("Gambler", &[Choice::Players(1), Choice::Character]),
+    ("Courtier", &[Choice::YesNo, Choice::Character]),
+    ("Professor", &[Choice::YesNo, Choice::Players(1)]),
+    ("Fang Gu", &[Choice::Players(1)]),
+    ("Vigormortis", &[Choice::Players(1)]),
+    ("No Dashii", &[Choice::Players(1)]),
+    ("Vortox", &[Choice::Players(1)]),
+    ("Witch", &[Choice::Players(1)]),
+    ("Cerenovus", &[Choice::Players(1), Choice::Character]),
+    ("Pit-Hag", &[Choice::Players(1), Choice::Character]),
+    ("Dreamer", &[Choice::Players(1)]),
+    ("Snake Charmer", &[Choice::Players(1)]),
+    ("Seamstress", &[Choice::YesNo, Choice::Players(2)]),
+    ("Philosopher", &[Choice::YesNo, Choice::Character]),
+    ("Harlot", &[Choice::Players(1)]),
+    ("Bone Collector", &[Choice::YesNo, Choice::Players(1)]),
+];
+
+// Discord allows this many options in one menu
+const MAX_OPTIONS: usize = 25;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ChoiceTiming {
+    Off,
+    Dusk,
+    Step,
+}
+
+impl ChoiceTiming {
+    pub fn as_str(&self) -> &str {
+        match *self {
+            ChoiceTiming::Off => "off",
+            ChoiceTiming::Dusk => "dusk",
+            ChoiceTiming::Step => "step",
+        }
+    }
+}
+
+// What the player thinks they are decides what they're asked, so a Drunk
+// Monk still picks someone to protect
+fn get_choices(character: &Character) -> Option<&'static [Choice]> {
+    let name = match &character.decoy_character {
+        Some(value) => &value.name,
+        None => &character.name,
+    };
+
+    NIGHT_CHOICES
+        .iter()
+        .find(|(choice_name, _)| choice_name == name)
+        .map(|(_, choices)| *choices)
+}
+
+fn get_shown_name(character: &Character) -> &str {
+    match &character.decoy_character {
+        Some(value) => &value.name,
+        None => &character.name,
+    }
+}
+
+struct ChoiceMenu {
+    custom_id: String,
+    placeholder: String,
+    options: Vec<(String, String)>,
+    count: u64,
+}
+
+// One menu per choice, except long character lists which are split over as
+// many as they need, where picking from any of them answers the choice. The
+// night is in the ID, so a menu left over from an earlier night won't work
+fn get_choice_menus(current_state: &BloodGuild, choices: &[Choice]) -> Vec<ChoiceMenu> {
+    let mut menus: Vec<ChoiceMenu> = Vec::new();
+
+    for (index, choice) in choices.iter().enumerate() {
+        let options: Vec<(String, String)> = match choice {
+            Choice::Players(_) => current_state
+                .roles
+                .iter()
+                .enumerate()
+                .map(|(seat, p)| {
+                    (
+                        format!("{}. {}", seat + 1, get_member_name(&p.1)),
+                        p.0.to_string(),
+                    )
+                })
+                .collect(),
+            Choice::Character => {
+                let mut characters = current_state.game_type.get_all_characters();
+                characters.retain(|c| !matches!(c.char_type, CharacterType::Traveler));
+                characters.sort_by(|a, b| a.name.cmp(&b.name));
+
+                characters
+                    .iter()
+                    .map(|c| (c.name.clone(), c.name.clone()))
+                    .collect()
+            }
+            Choice::YesNo => vec![
+                (String::from("Yes"), String::from("Yes")),
+                (String::from("No"), String::from("No")),
+            ],
+        };
+
+        let count = match choice {
+            Choice::Players(count) => (*count).min(options.len() as u64),
+            _ => 1,
+        };
+
+        // Choosing several players has to happen in one menu, so only characters are split
+        let parts: Vec<Vec<(String, String)>> = match choice {
+            Choice::Character => options.chunks(MAX_OPTIONS).map(|c| c.to_vec()).collect(),
+            _ => vec![options.into_iter().take(MAX_OPTIONS).collect()],
+        };
+
+        let part_count = parts.len();
+
+        for (part, options) in parts.into_iter().enumerate() {
+            let placeholder = match (options.first(), options.last()) {
+                (Some(first), Some(last)) if part_count > 1 => {
+                    format!("{} ({} to {})", choice.get_prompt(), first.0, last.0)
+                }
+                _ => choice.get_prompt(),
+            };
+
+            menus.push(ChoiceMenu {
+                custom_id: format!(
+                    "choice_{}_{}_{}_{}",
+                    current_state.id, current_state.day_index, index, part
+                ),
+                placeholder,
+                options,
+                count,
+            });
+        }
+    }
+
+    menus
+}
+
+fn create_choice_menus<'a>(
+    components: &'a mut CreateComponents,
+    menus: &[ChoiceMenu],
+) -> &'a mut CreateComponents {
+    for menu in menus {
+        components.create_action_row(|row| {
+            row.create_select_menu(|m| {
+                m.custom_id(&menu.custom_id)
+                    .placeholder(&menu.placeholder)
+                    .min_values(menu.count)
+                    .max_values(menu.count)
+                    .options(|o| {
+                        for (label, value) in &menu.options {
+                            o.create_option(|opt| opt.label(label).value(value));
+                        }
+                        o
+                    })
+            })
+        });
+    }
+
+    components
+}
+
+// Night choices are changed on the live state rather than a copy, as players
+// can answer at the same time as each other and as the storyteller's commands
+async fn set_live_choices(guild_id: u64, user_id: u64, answers: Option<Vec<Option<String>>>) {
+    if let Some(value) = BLOOD_DATABASE.lock().await.blood_guilds.get_mut(&guild_id) {
+        match answers {
+            Some(answers) => value.night_choices.insert(user_id, answers),
+            None => value.night_choices.remove(&user_id),
+        };
+    }
+}
+
+// DMs one player their menus, returning whether they were asked anything
+async fn send_choice_prompt(ctx: &Context, current_state: &BloodGuild, seat: usize) -> bool {
+    let (_, member, character) = &current_state.roles[seat];
+
+    let character = match character {
+        Some(value) => value,
+        None => return false,
+    };
+
+    let choices = match get_choices(character) {
+        Some(value) => value,
+        None => return false,
+    };
+
+    let content = format!(
+        "**Night {}:** As the **{}**, make your choice below.",
+        current_state.day_index,
+        get_shown_name(character)
+    );
+
+    let menus = get_choice_menus(current_state, choices);
+
+    // Too many seats for one menu, so the storyteller has to ask about the rest
+    if choices.iter().any(|c| matches!(c, Choice::Players(_)))
+        && current_state.roles.len() > MAX_OPTIONS
+    {
+        let warning = format!(
+            "**Warning:** Only the first {} seats fit in *{}*'s menu!",
+            MAX_OPTIONS,
+            get_member_name(member)
+        );
+
+        if let Err(why) = ChannelId(current_state.storyteller_channel)
+            .say(&ctx.http, warning)
+            .await
+        {
+            print_error(&format!("Could not send message: {:?}", why));
+        }
+    }
+
+    let result = match member.user.create_dm_channel(&ctx.http).await {
+        Ok(channel) => {
+            channel
+                .send_message(&ctx.http, |m| {
+                    m.content(content)
+                        .components(|c| create_choice_menus(c, &menus))
+                })
+                .await
+        }
+        Err(why) => Err(why),
+    };
+
+    if let Err(why) = result {
+        print_error(&format!(
+            "Could not send night choice to {}: {:?}",
+            get_member_name(member),
+            why
+        ));
+        return false;
+    }
+
+    true
+}
+
+/// Asks everyone who wakes tonight for their choice, if prompts go out at dusk.
+/// The dead are left out of the night order, unless dying is what wakes them
+pub async fn send_dusk_choices(ctx: &Context, msg: &impl CommandSource, guild_id: u64) {
+    let current_state = get_database(&guild_id).await;
+
+    if current_state.choice_prompts != ChoiceTiming::Dusk {
+        return;
+    }
+
+    let mut asked: Vec<String> = Vec::new();
+
+    for step in current_state.get_night_order() {
+        let user_id = match &step.player {
+            Some(value) => value.0,
+            None => continue,
+        };
+
+        let seat = match current_state.roles.iter().position(|p| p.0 == user_id) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        if current_state.night_choices.contains_key(&user_id) {
+            continue;
+        }
+
+        let count = get_choices(&step.character).map_or(0, |c| c.len());
+
+        // Saved before sending, so a quick answer isn't taken as an old one
+        set_live_choices(guild_id, user_id, Some(vec![None; count])).await;
+
+        if send_choice_prompt(ctx, &current_state, seat).await {
+            asked.push(get_member_name(&current_state.roles[seat].1));
+        } else {
+            set_live_choices(guild_id, user_id, None).await;
+        }
+    }
+
+    if !asked.is_empty() {
+        send_msg(
+            msg,
+            ctx,
+            format!("Sent night choices to {}", asked.join(", ")),
+        )
+        .await;
+    }
+}
+
+/// Asks the player woken by this step for their choice, if prompts go out step by step
+pub async fn send_step_choice(ctx: &Context, current_state: &mut BloodGuild, user_id: u64) {
+    if current_state.choice_prompts != ChoiceTiming::Step
+        || current_state.night_choices.contains_key(&user_id)
+    {
+        return;
+    }
+
+    let seat = match current_state.roles.iter().position(|p| p.0 == user_id) {
+        Some(value) => value,
+        None => return,
+    };
+
+    let count = current_state.roles[seat]
+        .2
+        .as_ref()
+        .and_then(get_choices)
+        .map_or(0, |c| c.len());
+
+    // Kept on the walkthrough's copy too, so it can show they're being asked
+    set_live_choices(current_state.id, user_id, Some(vec![None; count])).await;
+
+    if send_choice_prompt(ctx, current_state, seat).await {
+        current_state
+            .night_choices
+            .insert(user_id, vec![None; count]);
+    } else {
+        set_live_choices(current_state.id, user_id, None).await;
+    }
+}
+
+/// What a player has chosen so far tonight, for the night walkthrough
+pub fn get_choice_summary(current_state: &BloodGuild, user_id: u64) -> Option<String> {
+    let answers = current_state.night_choices.get(&user_id)?;
+
+    if answers.iter().all(|a| a.is_none()) {
+        return Some(String::from("*Waiting for their choice...*"));
+    }
+
+    Some(
+        answers
+            .iter()
+            .map(|a| a.clone().unwrap_or_else(|| String::from("*waiting*")))
+            .collect::<Vec<String>>()
+            .join(" / "),
+    )
+}
+
+pub async fn set_choice_prompts(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    match params.get(1).copied() {
+        Some("off") => current_state.choice_prompts = ChoiceTiming::Off,
+        Some("dusk") => current_state.choice_prompts = ChoiceTiming::Dusk,
+        Some("step") => current_state.choice_prompts = ChoiceTiming::Step,
+        None => (),
+        _ => {
+            send_msg(msg, ctx, String::from("Usage: \"choices off/dusk/step\"")).await;
+            return;
+        }
+    }
+
+    let content = format!(
+        "Night choices are sent to players: **{}**",
+        current_state.choice_prompts.as_str()
+    );
+
+    set_database(current_state).await;
+
+    send_msg(msg, ctx, content).await;
+}
+
+// The menus come from a DM, so the game is found from the menu itself
+pub async fn handle_choice(ctx: &Context, press: ComponentPress) {
+    let interaction: &MessageComponentInteraction = &press.interaction;
+
+    // Like "choice_[guild]_[night]_[choice]_[part]"
+    let parts: Vec<&str> = press.content.split('_').collect();
+
+    let (guild_id, night, index) = match (
+        parts.get(1).and_then(|p| p.parse::<u64>().ok()),
+        parts.get(2).and_then(|p| p.parse::<u32>().ok()),
+        parts.get(3).and_then(|p| p.parse::<usize>().ok()),
+    ) {
+        (Some(guild_id), Some(night), Some(index)) => (guild_id, night, index),
+        _ => return,
+    };
+
+    let user_id = *interaction.user.id.as_u64();
+
+    let current_state = match BLOOD_DATABASE.lock().await.blood_guilds.get(&guild_id) {
+        Some(value) => value.clone(),
+        None => {
+            send_msg(&press, ctx, String::from("That game has ended!")).await;
+            return;
+        }
+    };
+
+    let is_current = |state: &BloodGuild| {
+        matches!(state.time, Time::Night)
+            && state.day_index == night
+            && state
+                .night_choices
+                .get(&user_id)
+                .is_some_and(|answers| index < answers.len())
+    };
+
+    if !is_current(&current_state) {
+        send_msg(
+            &press,
+            ctx,
+            String::from("That choice is from an earlier night!"),
+        )
+        .await;
+        return;
+    }
+
+    let values = &interaction.data.values;
+
+    // Players are chosen by ID, so show their names instead
+    let answer: Vec<String> = values
+        .iter()
+        .map(|value| match value.parse::<u64>() {
+            Ok(id) => current_state
+                .roles
+                .iter()
+                .find(|p| p.0 == id)
+                .map_or(value.clone(), |p| get_member_name(&p.1)),
+            Err(_) => value.clone(),
+        })
+        .collect();
+
+    let answer = answer.join(" and ");
+
+    // Only this answer is written, straight into the live state, so players
+    // answering at the same time don't undo each other
+    {
+        let mut lock = BLOOD_DATABASE.lock().await;
+
+        match lock.blood_guilds.get_mut(&guild_id) {
+            Some(value) if is_current(value) => {
+                if let Some(answers) = value.night_choices.get_mut(&user_id) {
+                    answers[index] = Some(answer.clone());
+                }
+            }
+            _ => {
+                drop(lock);
+
+                send_msg(
+                    &press,
+                    ctx,
+                    String::from("That choice is from an earlier night!"),
+                )
+                .await;
+                return;
+            }
+        }
+    }
+
+    let (name, character) = match current_state.roles.iter().find(|p| p.0 == user_id) {
+        Some(value) => (
+            get_member_name(&value.1),
+            value.2.as_ref().map_or("", |c| c.name.as_str()).to_string(),
+        ),
+        None => return,
+    };
+
+    print_info(&format!("User {} chose {}", name, answer));
+
+    send_msg(&press, ctx, format!("You chose **{}**", answer)).await;
+
+    if let Err(why) = ChannelId(current_state.storyteller_channel)
+        .say(
+            &ctx.http,
+            format!("📝 *{}* ({}) chose **{}**", name, character, answer),
+        )
+        .await
+    {
+        print_error(&format!("Could not pass on night choice: {:?}", why));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::*;
+
+use serenity::model::interactions::message_component::ButtonStyle;
+
+// Deaths recorded with "kill" during the night are kept until dawn, so the
+// storyteller can check them and announce them to everyone in one go
+
+fn get_dawn_text(current_state: &BloodGuild) -> String {
+    let names: Vec<String> = current_state
+        .night_deaths
+        .iter()
+        .map(|id| format!("<@{}>", id))
+        .collect();
+
+    match names.len() {
+        0 => String::from("This morning, **nobody died**."),
+        1 => format!("This morning, {} **died**.", names[0]),
+        _ => format!(
+            "This morning, {} and {} **died**.",
+            names[..names.len() - 1].join(", "),
+            names[names.len() - 1]
+        ),
+    }
+}
+
+/// Shows the storyteller who died in the night, with buttons to announce it or hold it back
+pub async fn ask_to_announce_deaths(ctx: &Context, current_state: &BloodGuild) {
+    let preview = get_dawn_text(current_state);
+
+    let result = match current_state.public_channel {
+        Some(channel) => {
+            ChannelId(current_state.storyteller_channel)
+                .send_message(&ctx.http, |m| {
+                    m.embed(|e| {
+                        e.title("Dawn")
+                            .description(format!("{}\n\nAnnounce this in <#{}>?", preview, channel))
+                    })
+                    .components(|c| {
+                        c.create_action_row(|row| {
+                            row.create_button(|b| {
+                                b.custom_id("dawn_announce")
+                                    .label("Announce")
+                                    .style(ButtonStyle::Primary)
+                            })
+                            .create_button(|b| {
+                                b.custom_id("dawn_hold")
+                                    .label("Hold")
+                                    .style(ButtonStyle::Secondary)
+                            })
+                        })
+                    })
+                })
+                .await
+        }
+        None => {
+            ChannelId(current_state.storyteller_channel)
+                .send_message(&ctx.http, |m| {
+                    m.embed(|e| {
+                        e.title("Dawn").description(format!(
+                            "{}\n\nType \"public #channel\" and then \"dawn\" to announce this.",
+                            preview
+                        ))
+                    })
+                })
+                .await
+        }
+    };
+
+    if let Err(why) = result {
+        print_error(&format!("Could not ask to announce deaths: {:?}", why));
+    }
+}
+
+pub async fn announce_deaths(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    if !current_state.dawn_pending {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** There's no dawn announcement waiting!"),
+        )
+        .await;
+        return;
+    }
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    if params.get(1) == Some(&"hold") {
+        send_msg(
+            msg,
+            ctx,
+            String::from("Holding the dawn announcement. Type \"dawn\" when you're ready."),
+        )
+        .await;
+        return;
+    }
+
+    let channel = match current_state.public_channel {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from(
+                    "**Error:** No public channel is set! Type \"public #channel\" to set one.",
+                ),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let content = get_dawn_text(&current_state);
+
+    if let Err(why) = ChannelId(channel)
+        .send_message(&ctx.http, |m| m.content(&content))
+        .await
+    {
+        print_error(&format!("Could not announce deaths: {:?}", why));
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** Could not send the announcement!"),
+        )
+        .await;
+        return;
+    }
+
+    print_info(&format!(
+        "Announced {} deaths at dawn",
+        current_state.night_deaths.len()
+    ));
+    send_msg(
+        msg,
+        ctx,
+        format!("Announced in <#{}>: {}", channel, content),
+    )
+    .await;
+
+    current_state.night_deaths.clear();
+    current_state.dawn_pending = false;
+
+    // Now everyone knows, their nicknames and the status board can show it
+    update_nicknames(ctx, &mut current_state).await;
+
+    set_database(current_state).await;
+
+    update_status_board(ctx, *guild_id).await;
+}
+
+pub async fn handle_dawn_press(ctx: &Context, mut press: ComponentPress) {
+    press.content = match press.content.as_str() {
+        "dawn_hold" => String::from("dawn hold"),
+        _ => String::from("dawn"),
+    };
+
+    announce_deaths(ctx, &press).await;
+
+    // The choice is made, so the buttons go away
+    if let Err(why) = press
+        .interaction
+        .edit_original_interaction_response(&ctx.http, |r| r.components(|c| c))
+        .await
+    {
+        print_error(&format!("Could not remove dawn buttons: {:?}", why));
+    }
+}
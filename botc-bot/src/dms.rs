@@ -0,0 +1,216 @@
+use crate::*;
+
+// Commands players can DM the bot during a game, so they don't have to ask
+// the storyteller to repeat things. Everything here is either what they were
+// already told, or what anyone in the town square can see
+
+const DM_HELP: &str = "**You can DM me:**
+**role**: the character you were given
+**script**: every character on the script and their ability
+**ability [character]**: what a character does
+**status**: who's alive, who has ghost votes and the time
+**info**: everything the storyteller has told you
+**whisper [player]**: ask someone for a private whisper during the day";
+
+// The game this player is seated in, if any
+async fn find_player_game(user_id: u64) -> Option<BloodGuild> {
+    let lock = BLOOD_DATABASE.lock().await;
+
+    let mut games = lock
+        .blood_guilds
+        .values()
+        .filter(|g| g.roles.iter().any(|p| p.0 == user_id));
+
+    // If someone is somehow in two games, the one that has started wins
+    let first = games.next()?.clone();
+
+    Some(
+        games
+            .find(|g| matches!(g.game_state, GameState::Playing))
+            .cloned()
+            .unwrap_or(first),
+    )
+}
+
+fn get_player_role(current_state: &BloodGuild, user_id: u64) -> String {
+    if !matches!(current_state.game_state, GameState::Playing) {
+        return String::from("Roles haven't been sent out yet!");
+    }
+
+    let character = match current_state.roles.iter().find(|p| p.0 == user_id) {
+        Some((_, _, Some(value))) => value,
+        _ => return String::from("You don't have a role yet!"),
+    };
+
+    let mut content = get_role_message(current_state, character);
+
+    match &character.decoy_character {
+        Some(value) => content.push_str(&format!("\n*{}*", value.ability)),
+        None => {
+            content.push_str(&format!("\n*{}*", character.ability));
+
+            // Anyone whose alignment has changed was told so, so remind them
+            if let CharacterMatch::Found(original) =
+                current_state.game_type.find_character(&character.name)
+            {
+                if original.alignment.as_str() != character.alignment.as_str()
+                    && !matches!(character.char_type, CharacterType::Traveler)
+                {
+                    content.push_str(&format!("\nYou are **{}**.", character.alignment.as_str()));
+                }
+            }
+        }
+    }
+
+    content
+}
+
+// One message per character type, as a whole script is too long for one
+fn get_script_messages(current_state: &BloodGuild) -> Vec<String> {
+    let characters = current_state.game_type.get_all_characters();
+
+    let groups = [
+        ("Townsfolk", vec!["Townsfolk"]),
+        ("Outsiders", vec!["Outsider", "Decoy"]),
+        ("Minions", vec!["Minion"]),
+        ("Demons", vec!["Demon"]),
+        ("Travelers", vec!["Traveler"]),
+    ];
+
+    let mut messages = vec![format!("**{}**", current_state.game_type.get_name())];
+
+    for (heading, types) in groups.iter() {
+        let mut group: Vec<&Character> = characters
+            .iter()
+            .filter(|c| types.contains(&c.char_type_str.as_str()))
+            .collect();
+
+        if group.is_empty() {
+            continue;
+        }
+
+        group.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut content = format!("__**{}**__\n", heading);
+
+        for character in group {
+            content.push_str(&format!("**{}**: {}\n", character.name, character.ability));
+        }
+
+        messages.push(content);
+    }
+
+    messages
+}
+
+async fn get_ability(current_state: &BloodGuild, name: &str) -> String {
+    let found = match current_state.game_type.find_character(name) {
+        CharacterMatch::NotFound => BLOOD_DATABASE.lock().await.fabled.find_character(name),
+        value => value,
+    };
+
+    match found {
+        CharacterMatch::Found(character) => {
+            let mut content = format!(
+                "**{}** ({})\n*{}*",
+                character.name, character.char_type_str, character.ability
+            );
+
+            for jinx in current_state.game_type.get_script_jinxes() {
+                if let Some(other) = jinx.other(&character.name) {
+                    content.push_str(&format!("\n**Jinxed with the {}:** {}", other, jinx.rule));
+                }
+            }
+
+            content
+        }
+        CharacterMatch::Ambiguous(choices) => {
+            let names: Vec<String> = choices.iter().map(|c| format!("**{}**", c.name)).collect();
+
+            format!("Did you mean {}?", names.join(" or "))
+        }
+        CharacterMatch::NotFound => format!("Could not find {} on this script!", name),
+    }
+}
+
+pub async fn handle_player_dm(ctx: &Context, msg: &Message) {
+    let params: Vec<&str> = msg.content.split_whitespace().collect();
+
+    let command = match params.first() {
+        Some(value) => value.to_lowercase(),
+        None => return,
+    };
+
+    if !matches!(
+        command.as_str(),
+        "role" | "script" | "ability" | "status" | "info" | "whisper" | "help"
+    ) {
+        return;
+    }
+
+    print_command(msg);
+
+    let user_id = *msg.author.id.as_u64();
+
+    let current_state = match find_player_game(user_id).await {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("You aren't seated in a game right now!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    match command.as_str() {
+        "role" => send_msg(msg, ctx, get_player_role(&current_state, user_id)).await,
+        "script" => {
+            for content in get_script_messages(&current_state) {
+                send_msg(msg, ctx, content).await;
+            }
+        }
+        "ability" => {
+            let name = params[1..].join(" ");
+
+            if name.is_empty() {
+                send_msg(msg, ctx, String::from("Usage: \"ability [character]\"")).await;
+            } else {
+                send_msg(msg, ctx, get_ability(&current_state, &name).await).await;
+            }
+        }
+        "status" => {
+            send_embed(
+                msg,
+                ctx,
+                String::from("🩸 Town Square"),
+                get_board_description(&current_state),
+                None,
+            )
+            .await
+        }
+        "info" => {
+            let content = get_info_history(&current_state, user_id)
+                .unwrap_or_else(|| String::from("The storyteller hasn't told you anything yet."));
+
+            send_embed(
+                msg,
+                ctx,
+                String::from("What you've been told"),
+                content,
+                None,
+            )
+            .await
+        }
+        "whisper" => {
+            if matches!(current_state.game_state, GameState::Playing) {
+                request_whisper(ctx, msg, current_state.id, &params[1..].join(" ")).await;
+            } else {
+                send_msg(msg, ctx, String::from("The game hasn't started yet!")).await;
+            }
+        }
+        _ => send_msg(msg, ctx, String::from(DM_HELP)).await,
+    }
+}
@@ -0,0 +1,242 @@
+use crate::*;
+
+// Fabled are characters the storyteller adds to a game to handle special
+// situations, like new players (Angel) or big groups (Doomsayer)
+
+pub const FABLED_PATH: &str = "fabled/Fabled.csv";
+
+/// Lists the Fabled that can be added, shown when a game starts
+pub async fn get_fabled_summary() -> String {
+    let mut names: Vec<String> = BLOOD_DATABASE
+        .lock()
+        .await
+        .fabled
+        .get_all_characters()
+        .iter()
+        .map(|c| c.name.clone())
+        .collect();
+
+    names.sort();
+
+    format!(
+        "**Fabled:** {}\nType \"fabled add [name]\" to add one to this game.",
+        names.join(", ")
+    )
+}
+
+// The storyteller gets the full list here, and the players see it on the status board
+async fn announce_fabled(ctx: &Context, current_state: &BloodGuild) {
+    let content = if current_state.fabled.is_empty() {
+        String::from("No Fabled are in play.")
+    } else {
+        current_state
+            .fabled
+            .iter()
+            .map(|f| format!("**{}**\n*{}*", f.name, f.ability))
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    };
+
+    if let Err(why) = ChannelId(current_state.storyteller_channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.title("Fabled in play").description(content))
+        })
+        .await
+    {
+        print_error(&format!("Could not announce Fabled: {:?}", why));
+    }
+}
+
+pub async fn set_fabled(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let action = params.get(1).copied().unwrap_or("");
+    let name = params.get(2..).map(|n| n.join(" ")).unwrap_or_default();
+
+    if action != "add" && action != "remove" {
+        announce_fabled(ctx, &current_state).await;
+        return;
+    }
+
+    let all_fabled = BLOOD_DATABASE.lock().await.fabled.clone();
+
+    let fabled = match all_fabled.find_character(&name) {
+        CharacterMatch::Found(value) => value,
+        CharacterMatch::Ambiguous(choices) => {
+            let names: Vec<String> = choices.iter().map(|c| format!("**{}**", c.name)).collect();
+
+            send_msg(
+                msg,
+                ctx,
+                format!("Did you mean {}? Please try again!", names.join(" or ")),
+            )
+            .await;
+            return;
+        }
+        CharacterMatch::NotFound => {
+            send_msg(msg, ctx, format!("Could not find Fabled {}!", name)).await;
+            return;
+        }
+    };
+
+    if action == "add" {
+        if current_state.has_fabled(&fabled.name) {
+            send_msg(
+                msg,
+                ctx,
+                format!("The **{}** is already in play!", fabled.name),
+            )
+            .await;
+            return;
+        }
+
+        print_info(&format!("Added Fabled {}", fabled.name));
+
+        current_state.fabled.push(fabled);
+    } else {
+        if !current_state.has_fabled(&fabled.name) {
+            send_msg(msg, ctx, format!("The **{}** isn't in play!", fabled.name)).await;
+            return;
+        }
+
+        print_info(&format!("Removed Fabled {}", fabled.name));
+
+        current_state.fabled.retain(|f| f.name != fabled.name);
+    }
+
+    announce_fabled(ctx, &current_state).await;
+
+    set_database(current_state).await;
+
+    update_status_board(ctx, *guild_id).await;
+}
+
+pub async fn fiddler_vote(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    if !current_state.has_fabled("Fiddler") {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** The Fiddler isn't in play!"),
+        )
+        .await;
+        return;
+    }
+
+    if current_state.fiddler_used {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** The Fiddler has already been used this game!"),
+        )
+        .await;
+        return;
+    }
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seats = (
+        params.get(1).and_then(|p| current_state.find_seat(p)),
+        params.get(2).and_then(|p| current_state.find_seat(p)),
+    );
+
+    let (first, second) = match seats {
+        (Some(first), Some(second)) if first != second => (first, second),
+        _ => {
+            send_msg(
+                msg,
+                ctx,
+                String::from(
+                    "Please give the Demon's seat and the seat they chose, like \"fiddler 3 7\"!",
+                ),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let names = (
+        get_member_name(&current_state.roles[first].1),
+        get_member_name(&current_state.roles[second].1),
+    );
+
+    let votes = (
+        params.get(3).and_then(|v| v.parse::<usize>().ok()),
+        params.get(4).and_then(|v| v.parse::<usize>().ok()),
+    );
+
+    // Without the counts, just explain what everyone is voting on
+    let (first_votes, second_votes) = match votes {
+        (Some(first_votes), Some(second_votes)) => (first_votes, second_votes),
+        _ => {
+            send_msg(
+                msg,
+                ctx,
+                format!(
+                    "**The Fiddler!** Everyone chooses whether *{}* or *{}* wins, and their team wins the game. Type \"fiddler {} {} [votes for {}] [votes for {}]\" with the counts.",
+                    names.0,
+                    names.1,
+                    first + 1,
+                    second + 1,
+                    names.0,
+                    names.1
+                ),
+            )
+            .await;
+            return;
+        }
+    };
+
+    if first_votes == second_votes {
+        send_msg(
+            msg,
+            ctx,
+            format!("It's a tie at {} votes each, so vote again!", first_votes),
+        )
+        .await;
+        return;
+    }
+
+    let winner = if first_votes > second_votes {
+        first
+    } else {
+        second
+    };
+
+    let team = match &current_state.roles[winner].2 {
+        Some(value) => value.alignment.as_str().to_lowercase(),
+        None => String::from("unknown"),
+    };
+
+    current_state.fiddler_used = true;
+
+    print_info(&format!(
+        "Fiddler vote won by {}",
+        get_member_name(&current_state.roles[winner].1)
+    ));
+    send_msg(
+        msg,
+        ctx,
+        format!(
+            "*{}* wins the Fiddler vote ({} to {}), so the **{}** team wins!",
+            get_member_name(&current_state.roles[winner].1),
+            first_votes.max(second_votes),
+            first_votes.min(second_votes),
+            team
+        ),
+    )
+    .await;
+
+    set_database(current_state).await;
+}
@@ -6,6 +6,15 @@ pub enum Alignment {
     Evil,
 }
 
+impl Alignment {
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Alignment::Good => "Good",
+            Alignment::Evil => "Evil",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum ActionTime {
     OnlyFirstNight,
@@ -66,6 +75,10 @@ pub struct Character {
     pub first_order_index: i32,
     pub order_index: i32,
     pub night_action: ActionTime,
+    pub ability: String,
+    pub reminders: Vec<String>,
+    // The reminders that are taken off at dawn, like the Monk's "Safe"
+    pub night_reminders: Vec<String>,
     pub decoy_character: Option<DecoyCharacter>,
 }
 
@@ -75,6 +88,7 @@ pub struct DecoyCharacter {
     pub alignment: Alignment,
     pub char_type: CharacterType,
     pub char_type_str: String,
+    pub ability: String,
 }
 
 impl Character {
@@ -84,6 +98,8 @@ impl Character {
         first_order_index: i32,
         order_index: i32,
         night_action: ActionTime,
+        ability: String,
+        reminders: Vec<String>,
     ) -> Self {
 
         let alignment: Alignment;
@@ -104,6 +120,9 @@ impl Character {
             first_order_index: first_order_index,
             order_index: order_index,
             night_action: night_action,
+            ability,
+            reminders,
+            night_reminders: Vec::new(),
             decoy_character: None,
         }
     }
@@ -117,34 +136,191 @@ impl Character {
             first_order_index: character.first_order_index,
             order_index: character.order_index,
             night_action: character.night_action,
+            ability: character.ability,
+            reminders: character.reminders,
+            night_reminders: character.night_reminders,
             decoy_character: Some(decoy),
         }
     }
+    pub fn to_decoy(&self) -> DecoyCharacter {
+        DecoyCharacter {
+            name: self.name.clone(),
+            alignment: self.alignment.clone(),
+            char_type: self.char_type.clone(),
+            char_type_str: self.char_type_str.clone(),
+            ability: self.ability.clone(),
+        }
+    }
+
     pub fn get_string(&self) -> String {
         return format!("{: <18}| {: <15}| {: <25}", self.name, self.char_type.as_str(), self.night_action.as_str())
     }
 
 }
 
+// A special rule for when two characters are in the same game
+#[derive(Clone)]
+pub struct Jinx {
+    pub first: String,
+    pub second: String,
+    pub rule: String,
+}
+
+impl Jinx {
+    /// The other character, if this jinx involves the given one
+    pub fn other(&self, character_name: &str) -> Option<&str> {
+        if self.first == character_name {
+            Some(&self.second)
+        } else if self.second == character_name {
+            Some(&self.first)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct GameType {
     name: String,
     roles: HashMap<String, Character>,
+    aliases: HashMap<String, String>,
+    jinxes: Vec<Jinx>,
+}
+
+// What a storyteller's typed role turned into
+pub enum CharacterMatch {
+    Found(Character),
+    Ambiguous(Vec<Character>),
+    NotFound,
+}
+
+// Lowercase with only letters and numbers, so "devils advocate" matches "Devil’s Advocate"
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .flat_map(|c| c.to_lowercase())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
 }
 
 impl GameType {
     pub fn new(
         name: String,
         roles: HashMap<String, Character>,
+        aliases: HashMap<String, String>,
     ) -> Self {
         GameType {
             name: name,
             roles: roles,
+            aliases: aliases
+                .into_iter()
+                .map(|(alias, character_name)| (normalize_name(&alias), character_name))
+                .collect(),
+            jinxes: Vec::new(),
         }
     }
 
-    pub fn get_character(&self, character_name: String) -> Character {
-        return self.roles.get(&character_name).unwrap().clone()
+    /// Keeps the jinxes with a character on this script. The other character
+    /// might still end up in play, so whether a jinx applies is up to the game
+    pub fn set_jinxes(&mut self, all_jinxes: &[Jinx]) {
+        self.jinxes = all_jinxes
+            .iter()
+            .filter(|j| self.roles.contains_key(&j.first) || self.roles.contains_key(&j.second))
+            .cloned()
+            .collect();
+    }
+
+    pub fn get_jinxes(&self) -> &Vec<Jinx> {
+        &self.jinxes
+    }
+
+    /// The jinxes where both characters are on this script
+    pub fn get_script_jinxes(&self) -> Vec<&Jinx> {
+        self.jinxes
+            .iter()
+            .filter(|j| self.roles.contains_key(&j.first) && self.roles.contains_key(&j.second))
+            .collect()
+    }
+
+    pub fn get_character(&self, character_name: &str) -> Option<Character> {
+        self.roles.get(character_name).cloned()
+    }
+
+    /// Finds the character a storyteller meant, trying an exact name, then an alias,
+    /// then the start of the name (or of one of its words), then close misspellings.
+    /// Only the best kind of match counts, and if several tie they're all returned
+    pub fn find_character(&self, input: &str) -> CharacterMatch {
+        let input = normalize_name(input);
+
+        if input.is_empty() {
+            return CharacterMatch::NotFound;
+        }
+
+        // Short inputs only get one typo, so "po" doesn't match every 3 letter name
+        let max_distance = if input.len() >= 5 { 2 } else { 1 };
+
+        let mut best_rank = usize::MAX;
+        let mut best: Vec<Character> = Vec::new();
+
+        for character in self.roles.values() {
+            let name = normalize_name(&character.name);
+
+            let rank = if name == input {
+                0
+            } else if self.aliases.get(&input) == Some(&character.name) {
+                1
+            } else if name.starts_with(&input) {
+                2
+            } else if name.split(' ').any(|word| word.starts_with(&input)) {
+                3
+            } else {
+                let distance = edit_distance(&input, &name);
+
+                if input.len() < 3 || distance > max_distance {
+                    continue;
+                }
+
+                3 + distance
+            };
+
+            if rank < best_rank {
+                best_rank = rank;
+                best.clear();
+            }
+
+            if rank == best_rank {
+                best.push(character.clone());
+            }
+        }
+
+        best.sort_by(|a, b| a.name.cmp(&b.name));
+
+        match best.len() {
+            0 => CharacterMatch::NotFound,
+            1 => CharacterMatch::Found(best.remove(0)),
+            _ => CharacterMatch::Ambiguous(best),
+        }
     }
 
     pub fn get_all_characters(&self) -> Vec<Character> {
@@ -160,4 +336,144 @@ impl GameType {
     pub fn get_name(&self) -> String {
         return self.name.clone()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_game() -> GameType {
+        let mut roles = HashMap::new();
+
+        for name in ["Chef", "Chambermaid", "Empath", "Fortune Teller", "Monk"] {
+            roles.insert(
+                String::from(name),
+                Character::new(
+                    String::from(name),
+                    CharacterType::Townsfolk,
+                    -1,
+                    -1,
+                    ActionTime::NoNight,
+                    String::new(),
+                    Vec::new(),
+                ),
+            );
+        }
+
+        let mut aliases = HashMap::new();
+        aliases.insert(String::from("FT"), String::from("Fortune Teller"));
+
+        GameType::new(String::from("Test"), roles, aliases)
+    }
+
+    fn found_name(found: CharacterMatch) -> Option<String> {
+        match found {
+            CharacterMatch::Found(character) => Some(character.name),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn finds_exact_name() {
+        let game = test_game();
+
+        assert_eq!(
+            found_name(game.find_character("chef")),
+            Some(String::from("Chef"))
+        );
+        assert_eq!(
+            found_name(game.find_character("  Fortune  teller ")),
+            Some(String::from("Fortune Teller"))
+        );
+    }
+
+    #[test]
+    fn finds_alias() {
+        assert_eq!(
+            found_name(test_game().find_character("ft")),
+            Some(String::from("Fortune Teller"))
+        );
+    }
+
+    #[test]
+    fn finds_prefix_and_word_prefix() {
+        let game = test_game();
+
+        assert_eq!(
+            found_name(game.find_character("emp")),
+            Some(String::from("Empath"))
+        );
+        assert_eq!(
+            found_name(game.find_character("teller")),
+            Some(String::from("Fortune Teller"))
+        );
+    }
+
+    #[test]
+    fn finds_misspelling() {
+        assert_eq!(
+            found_name(test_game().find_character("empth")),
+            Some(String::from("Empath"))
+        );
+    }
+
+    #[test]
+    fn tie_is_ambiguous() {
+        match test_game().find_character("ch") {
+            CharacterMatch::Ambiguous(choices) => {
+                let names: Vec<&str> = choices.iter().map(|c| c.name.as_str()).collect();
+
+                assert_eq!(names, vec!["Chambermaid", "Chef"]);
+            }
+            _ => panic!("\"ch\" should match both the Chef and Chambermaid"),
+        }
+    }
+
+    #[test]
+    fn unknown_is_not_found() {
+        let game = test_game();
+
+        assert!(matches!(
+            game.find_character("xyz"),
+            CharacterMatch::NotFound
+        ));
+        assert!(matches!(game.find_character(""), CharacterMatch::NotFound));
+    }
+
+    // Every jinx has to be about characters the bot knows, or it can never apply
+    #[test]
+    fn jinxes_name_script_characters() {
+        let mut names: Vec<String> = Vec::new();
+
+        for entry in std::fs::read_dir("games").unwrap() {
+            let mut rdr = csv::Reader::from_path(entry.unwrap().path()).unwrap();
+
+            for record in rdr.records() {
+                names.push(String::from(record.unwrap().get(0).unwrap()));
+            }
+        }
+
+        let jinxes = crate::load_jinxes();
+
+        assert!(!jinxes.is_empty());
+
+        for jinx in &jinxes {
+            assert!(
+                names.contains(&jinx.first),
+                "{} isn't on a script",
+                jinx.first
+            );
+            assert!(
+                names.contains(&jinx.second),
+                "{} isn't on a script",
+                jinx.second
+            );
+        }
+    }
+
+    #[test]
+    fn counts_edits() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("monk", "monk"), 0);
+    }
+}
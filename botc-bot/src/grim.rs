@@ -0,0 +1,262 @@
+use crate::*;
+
+// The storyteller's view of the whole game, one line per seat
+
+// Discord won't take an embed description any longer than this
+const EMBED_LIMIT: usize = 4096;
+
+fn get_seat_line(current_state: &BloodGuild, seat: usize) -> String {
+    let (user_id, member, character) = &current_state.roles[seat];
+
+    let life = if current_state.is_alive(*user_id) {
+        "Alive"
+    } else if current_state.used_ghost_votes.contains(user_id) {
+        "💀 Dead, vote used"
+    } else {
+        "💀 Dead, has ghost vote"
+    };
+
+    let mut line = match character {
+        Some(value) => {
+            let believes = match &value.decoy_character {
+                Some(decoy) => format!(", believes they are the **{}**", decoy.name),
+                None => String::new(),
+            };
+
+            format!(
+                "{}) **{}**: {} **{}**{}. {}",
+                seat + 1,
+                get_member_name(member),
+                value.alignment.as_str(),
+                value.name,
+                believes,
+                life
+            )
+        }
+        None => format!(
+            "{}) **{}**: No role yet. {}",
+            seat + 1,
+            get_member_name(member),
+            life
+        ),
+    };
+
+    if let Some(tokens) = get_tokens_str(current_state, *user_id) {
+        line.push_str(&format!("\n    Tokens: {}", tokens));
+    }
+
+    if let Some(note) = current_state.notes.get(user_id) {
+        line.push_str(&format!("\n    Notes: *{}*", note));
+    }
+
+    line
+}
+
+// Splits the lines into as few embeds as fit, cutting short any single line too long for one
+fn split_pages(lines: Vec<String>) -> Vec<String> {
+    let mut pages: Vec<String> = Vec::new();
+    let mut page = String::new();
+
+    for line in lines {
+        let line = if line.chars().count() >= EMBED_LIMIT {
+            let mut cut: String = line.chars().take(EMBED_LIMIT - 2).collect();
+            cut.push('…');
+            cut
+        } else {
+            line
+        };
+
+        if page.chars().count() + line.chars().count() + 1 > EMBED_LIMIT {
+            pages.push(page);
+            page = String::new();
+        }
+
+        page.push_str(&line);
+        page.push('\n');
+    }
+
+    pages.push(page);
+
+    pages
+}
+
+async fn send_image(ctx: &Context, channel: u64, png: Vec<u8>, content: String) -> bool {
+    let file = serenity::http::AttachmentType::Bytes {
+        data: png.into(),
+        filename: String::from("grimoire.png"),
+    };
+
+    if let Err(why) = ChannelId(channel)
+        .send_files(&ctx.http, vec![file], |m| m.content(content))
+        .await
+    {
+        print_error(&format!("Could not send grimoire image: {:?}", why));
+        return false;
+    }
+
+    true
+}
+
+// "grim image" goes to the storyteller channel, while "grim public" leaves out
+// everything hidden and goes to the public channel
+async fn show_grimoire_image(
+    ctx: &Context,
+    msg: &impl CommandSource,
+    current_state: &BloodGuild,
+    view: GrimView,
+) {
+    let channel = match view {
+        GrimView::Storyteller => current_state.storyteller_channel,
+        GrimView::Public => match current_state.public_channel {
+            Some(value) => value,
+            None => {
+                send_msg(
+                    msg,
+                    ctx,
+                    String::from(
+                        "**Error:** No public channel is set! Type \"public #channel\" to set one.",
+                    ),
+                )
+                .await;
+                return;
+            }
+        },
+    };
+
+    let png = match render_grimoire(current_state, view) {
+        Ok(value) => value,
+        Err(why) => {
+            print_error(&why);
+            send_msg(msg, ctx, format!("**Error:** {}", why)).await;
+            return;
+        }
+    };
+
+    let content = match view {
+        GrimView::Storyteller => format!("**Grimoire** ({})", current_state.get_time_str()),
+        GrimView::Public => format!("**Town Square** ({})", current_state.get_time_str()),
+    };
+
+    if send_image(ctx, channel, png, content).await && view == GrimView::Public {
+        send_msg(
+            msg,
+            ctx,
+            format!("Posted the town square in <#{}>", channel),
+        )
+        .await;
+    }
+}
+
+pub async fn show_grimoire(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let current_state = get_database(guild_id).await;
+
+    if current_state.roles.is_empty() {
+        send_msg(msg, ctx, String::from("**Error:** No roles have been set!")).await;
+        return;
+    }
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    match params.get(1).copied() {
+        Some("image") => {
+            show_grimoire_image(ctx, msg, &current_state, GrimView::Storyteller).await;
+            return;
+        }
+        Some("public") => {
+            show_grimoire_image(ctx, msg, &current_state, GrimView::Public).await;
+            return;
+        }
+        _ => (),
+    }
+
+    let mut lines: Vec<String> = (0..current_state.roles.len())
+        .map(|seat| get_seat_line(&current_state, seat))
+        .collect();
+
+    if !current_state.fabled.is_empty() {
+        let fabled: Vec<&str> = current_state
+            .fabled
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+
+        lines.push(format!("\n**Fabled:** {}", fabled.join(", ")));
+    }
+
+    let alive = current_state
+        .roles
+        .iter()
+        .filter(|p| current_state.is_alive(p.0))
+        .count();
+
+    let title = format!(
+        "Grimoire: {} ({} alive of {})",
+        current_state.game_state.as_str(),
+        alive,
+        current_state.roles.len()
+    );
+
+    // Lots of tokens and notes can be too much for one embed, so it's split over several
+    let pages = split_pages(lines);
+    let count = pages.len();
+
+    for (index, content) in pages.into_iter().enumerate() {
+        let title = if index == 0 {
+            title.clone()
+        } else {
+            format!("Grimoire (continued {}/{})", index + 1, count)
+        };
+
+        let footer = if index + 1 == count {
+            Some(current_state.get_time_str())
+        } else {
+            None
+        };
+
+        send_embed(msg, ctx, title, content, footer).await;
+    }
+}
+
+pub async fn set_note(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seat = match params.get(1).and_then(|p| current_state.find_seat(p)) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please give a seat and a note, like \"note 3 claims Chef\"!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let user_id = current_state.roles[seat].0;
+    let name = get_member_name(&current_state.roles[seat].1);
+    let note = params.get(2..).map(|n| n.join(" ")).unwrap_or_default();
+
+    // An empty note clears it
+    if note.is_empty() {
+        current_state.notes.remove(&user_id);
+
+        send_msg(msg, ctx, format!("Cleared the note on *{}*", name)).await;
+    } else {
+        send_msg(msg, ctx, format!("Noted for *{}*: *{}*", name, note)).await;
+
+        current_state.notes.insert(user_id, note);
+    }
+
+    set_database(current_state).await;
+}
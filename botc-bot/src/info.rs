@@ -0,0 +1,200 @@
+use crate::*;
+
+// Information the storyteller gives players is DMed and kept, so nobody has
+// to rely on remembering what they were told three nights ago
+
+#[derive(Clone)]
+pub struct InfoEntry {
+    pub when: String,
+    pub character: String,
+    pub text: String,
+}
+
+impl InfoEntry {
+    pub fn get_string(&self) -> String {
+        format!(
+            "**{}** (as the {}): {}",
+            self.when, self.character, self.text
+        )
+    }
+}
+
+// Characters whose info is a single answer, so "tell 3 2" is enough
+const INFO_TEMPLATES: &[(&str, &str)] = &[
+    ("Chef", "Pairs of evil players sitting together: **{}**"),
+    ("Empath", "Evil living neighbours: **{}**"),
+    ("Fortune Teller", "Is one of them the Demon? **{}**"),
+    ("Undertaker", "Today's executed player was the **{}**"),
+    ("Ravenkeeper", "That player is the **{}**"),
+    (
+        "Chambermaid",
+        "Players who woke tonight because of their ability: **{}**",
+    ),
+    ("Grandmother", "Your grandchild is **{}**"),
+    (
+        "Clockmaker",
+        "Steps from the Demon to its nearest Minion: **{}**",
+    ),
+    ("Dreamer", "That player is one of these: **{}**"),
+    ("Seamstress", "Are they the same alignment? **{}**"),
+    (
+        "Mathematician",
+        "Players whose abilities went wrong: **{}**",
+    ),
+    ("Flowergirl", "Did the Demon vote today? **{}**"),
+    ("Town Crier", "Did a Minion nominate today? **{}**"),
+    ("Oracle", "Evil dead players: **{}**"),
+    ("Juggler", "Correct guesses: **{}**"),
+    ("Sage", "One of these players is the Demon: **{}**"),
+];
+
+fn get_template(character: &str) -> Option<&'static str> {
+    INFO_TEMPLATES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(character))
+        .map(|(_, template)| *template)
+}
+
+// "chef: 1" uses the Chef template, and a one word answer like "2" or "yes"
+// uses the template of the character the player believes they are
+fn fill_template(shown_name: &str, text: &str) -> String {
+    if let Some((name, value)) = text.split_once(':') {
+        if let Some(template) = get_template(name.trim()) {
+            return template.replace("{}", value.trim());
+        }
+    }
+
+    match get_template(shown_name) {
+        Some(template) if !text.contains(' ') => template.replace("{}", text),
+        _ => text.to_string(),
+    }
+}
+
+/// Everything a player has been told, oldest first
+pub fn get_info_history(current_state: &BloodGuild, user_id: u64) -> Option<String> {
+    let entries = current_state.info_log.get(&user_id)?;
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    Some(
+        entries
+            .iter()
+            .map(|e| e.get_string())
+            .collect::<Vec<String>>()
+            .join("\n"),
+    )
+}
+
+pub async fn tell_info(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seat = match params.get(1).and_then(|p| current_state.find_seat(p)) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please give a seat and what to tell them, like \"tell 3 2\" or \"tell 3 chef: 1\"!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let (user_id, member, character) = current_state.roles[seat].clone();
+    let name = get_member_name(&member);
+    let text = params.get(2..).map(|t| t.join(" ")).unwrap_or_default();
+
+    // Without any text, show what they've been told so far
+    if text.is_empty() {
+        let content = get_info_history(&current_state, user_id)
+            .unwrap_or_else(|| String::from("Nothing yet."));
+
+        send_embed(msg, ctx, format!("Told to {}", name), content, None).await;
+        return;
+    }
+
+    let shown_name = match &character {
+        Some(value) => match &value.decoy_character {
+            Some(decoy) => decoy.name.clone(),
+            None => value.name.clone(),
+        },
+        None => String::from("no role"),
+    };
+
+    let entry = InfoEntry {
+        when: format!(
+            "{} {}",
+            current_state.time.as_str(),
+            current_state.day_index
+        ),
+        character: shown_name.clone(),
+        text: fill_template(&shown_name, &text),
+    };
+
+    // Only what actually reached them goes in the log
+    if !send_dm(ctx, msg, &member, entry.get_string(), "their info").await {
+        return;
+    }
+
+    print_info(&format!("Told {}: {}", name, entry.text));
+    send_msg(msg, ctx, format!("Told *{}*: {}", name, entry.text)).await;
+
+    current_state
+        .info_log
+        .entry(user_id)
+        .or_default()
+        .push(entry);
+
+    set_database(current_state).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_the_named_template() {
+        assert_eq!(
+            fill_template("Empath", "chef: 1"),
+            "Pairs of evil players sitting together: **1**"
+        );
+        assert_eq!(
+            fill_template("Empath", "Fortune Teller:  yes "),
+            "Is one of them the Demon? **yes**"
+        );
+    }
+
+    #[test]
+    fn fills_the_shown_character_template() {
+        assert_eq!(
+            fill_template("Empath", "2"),
+            "Evil living neighbours: **2**"
+        );
+        assert_eq!(
+            fill_template("fortune teller", "no"),
+            "Is one of them the Demon? **no**"
+        );
+    }
+
+    #[test]
+    fn keeps_free_text() {
+        assert_eq!(
+            fill_template("Empath", "You learn nothing tonight"),
+            "You learn nothing tonight"
+        );
+        assert_eq!(fill_template("Washerwoman", "3"), "3");
+        assert_eq!(
+            fill_template("Empath", "note: the Drunk is 4"),
+            "note: the Drunk is 4"
+        );
+    }
+}
@@ -1,7 +1,41 @@
 mod banners;
+mod board;
+mod changes;
+mod choices;
+mod dawn;
+mod dms;
+mod fabled;
 mod games;
-
+mod grim;
+mod info;
+mod moves;
+mod panel;
+mod players;
+mod render;
+mod role_menus;
+mod slash;
+mod timers;
+mod tokens;
+mod whispers;
+
+use board::*;
+use changes::*;
+use choices::*;
+use dawn::*;
+use dms::*;
+use fabled::*;
 use games::*;
+use grim::*;
+use info::*;
+use moves::*;
+use panel::*;
+use players::*;
+use render::*;
+use role_menus::*;
+use slash::*;
+use timers::*;
+use tokens::*;
+use whispers::*;
 use std::{collections::*, env, sync::Arc};
 use tokio::sync::Mutex;
 use lazy_static::lazy_static;
@@ -12,11 +46,15 @@ use serenity::{framework::standard::{
 use serenity::{
     async_trait, client::bridge::gateway::ShardManager, client::*, prelude::*,
 };
-use serenity::model::{channel::*, event::*, gateway::*, guild::*, id::*};
+use serenity::http::Http;
+use serenity::model::interactions::Interaction;
+use serenity::model::{
+    channel::*, event::*, gateway::*, guild::*, id::*, permissions::*, user::*, voice::*,
+};
 use serenity_utils::prompt::reaction_prompt;
 
 use colored::*;
-use csv::Reader;
+use csv::{Reader, Writer};
 
 pub struct ShardManagerContainer;
 
@@ -26,8 +64,8 @@ impl TypeMapKey for ShardManagerContainer {
 
 struct Handler;
 
-async fn has_discord_role(ctx: &Context, msg: &Message, role_string: &str) -> bool {
-    let roles: &Vec<RoleId> = &msg.member.as_ref().unwrap().roles;
+async fn has_discord_role(ctx: &Context, msg: &impl CommandSource, role_string: &str) -> bool {
+    let roles: &Vec<RoleId> = msg.member_roles().unwrap();
     let mut is_role: bool = false;
 
     for role in roles {
@@ -51,14 +89,35 @@ async fn has_discord_role(ctx: &Context, msg: &Message, role_string: &str) -> bo
 
 #[async_trait]
 impl EventHandler for Handler {
-    async fn ready(&self, _: Context, ready: Ready) {
+    async fn ready(&self, ctx: Context, ready: Ready) {
         print_status(&format!("Connected as {}", ready.user.name));
+
+        // Any nicknames still saved are from games that were running when the bot stopped
+        restore_saved_nicknames(&ctx).await;
+
+        register_commands(&ctx).await;
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        handle_interaction(&ctx, interaction).await;
     }
 
     async fn resume(&self, _: Context, _: ResumedEvent) {
         print_info("Resumed");
     }
 
+    async fn voice_state_update(
+        &self,
+        ctx: Context,
+        guild_id: Option<GuildId>,
+        old: Option<VoiceState>,
+        new: VoiceState,
+    ) {
+        if let Some(guild_id) = guild_id {
+            watch_voice_state(&ctx, guild_id, old, new).await;
+        }
+    }
+
     async fn message(&self, ctx: Context, msg: Message) {
         // First, check to see if the message is a command. If it's not, discard
         if !msg.content.starts_with("~") {
@@ -96,7 +155,20 @@ impl EventHandler for Handler {
 
                             drop(state);
 
-                            if is_rolling {
+                            // These work while roles are being set, so they aren't taken as a role
+                            let first_word = msg.content.split_whitespace().next().unwrap_or("");
+                            let is_setup_command =
+                                matches!(first_word, "assign" | "add" | "remove" | "replace" | "traveler");
+
+                            if is_setup_command {
+                                match first_word {
+                                    "assign" => assign_roles(&ctx, &msg).await,
+                                    "add" => add_player(&ctx, &msg).await,
+                                    "remove" => remove_player(&ctx, &msg).await,
+                                    "traveler" => add_traveler(&ctx, &msg).await,
+                                    _ => replace_player(&ctx, &msg).await,
+                                }
+                            } else if is_rolling {
                                 roles(&ctx, &msg).await;
                             } else {
                                 if msg.content.len() > 0 {
@@ -111,6 +183,31 @@ impl EventHandler for Handler {
                                         "day" => day(&ctx, &msg).await,
                                         "wake" => day(&ctx, &msg).await,
                                         "edit" => edit_role(&ctx, &msg).await,
+                                        "next" => night_walkthrough(&ctx, &msg, Walk::Next).await,
+                                        "back" => night_walkthrough(&ctx, &msg, Walk::Back).await,
+                                        "skip" => night_walkthrough(&ctx, &msg, Walk::Skip).await,
+                                        "watch" => set_watchdog(&ctx, &msg).await,
+                                        "lock" => set_locked_channels(&ctx, &msg).await,
+                                        "nightmute" => set_night_mute(&ctx, &msg).await,
+                                        "kill" => set_life(&ctx, &msg, false).await,
+                                        "revive" => set_life(&ctx, &msg, true).await,
+                                        "vote" => use_ghost_vote(&ctx, &msg).await,
+                                        "exile" => exile_traveler(&ctx, &msg).await,
+                                        "fabled" => set_fabled(&ctx, &msg).await,
+                                        "fiddler" => fiddler_vote(&ctx, &msg).await,
+                                        "become" => become_character(&ctx, &msg).await,
+                                        "swap" => swap_characters(&ctx, &msg).await,
+                                        "align" => set_alignment(&ctx, &msg).await,
+                                        "token" => set_token(&ctx, &msg).await,
+                                        "grim" => show_grimoire(&ctx, &msg).await,
+                                        "note" => set_note(&ctx, &msg).await,
+                                        "public" => set_public_channel(&ctx, &msg).await,
+                                        "dawn" => announce_deaths(&ctx, &msg).await,
+                                        "choices" => set_choice_prompts(&ctx, &msg).await,
+                                        "tell" => tell_info(&ctx, &msg).await,
+                                        "whisper" => set_whisper(&ctx, &msg).await,
+                                        "timer" => set_timer(&ctx, &msg).await,
+                                        "nicknames" => set_nicknames(&ctx, &msg).await,
                                         _ => nothing(&ctx, &msg).await,
                                     }
                                 }
@@ -118,6 +215,9 @@ impl EventHandler for Handler {
                         }
                     }
                 }
+            } else if !msg.author.bot {
+                // Players can DM the bot to ask about their own game
+                handle_player_dm(&ctx, &msg).await;
             }
         }
     }
@@ -160,14 +260,14 @@ fn print_echo(msg: &Message) {
     );
 }
 
-fn print_command(msg: &Message) {
+fn print_command(msg: &impl CommandSource) {
     println!(
         "{} █ [{}] {} {}#{}",
         "COMMAND".yellow().bold(),
-        &msg.content.purple(),
+        &msg.content().purple(),
         "by".yellow().italic(),
-        &msg.author.name,
-        &msg.author.discriminator
+        &msg.author().name,
+        &msg.author().discriminator
     );
 }
 
@@ -176,17 +276,129 @@ fn print_error(msg: &str) {
 }
 
 // Function to send a message to a channel safely
-async fn send_msg(msg: &Message, ctx: &Context, content: String) {
-    if let Err(why) = &msg.channel_id.say(&ctx.http, content).await {
+async fn send_msg(msg: &impl CommandSource, ctx: &Context, content: String) {
+    if let Err(why) = &msg.say(ctx, content).await {
         print_error(&format!("Could not send message: {:?}", why));
     }
 }
 
+// Function to send an embed to a channel safely
+async fn send_embed(
+    msg: &impl CommandSource,
+    ctx: &Context,
+    title: String,
+    description: String,
+    footer: Option<String>,
+) {
+    if let Err(why) = &msg.say_embed(ctx, title, description, footer).await {
+        print_error(&format!("Could not send embed: {:?}", why));
+    }
+}
+
+// Anything a command can come from. Typed commands reply in the channel they
+// were sent in, while slash commands reply privately to the storyteller
+#[async_trait]
+pub trait CommandSource: Sync {
+    fn guild_id(&self) -> Option<GuildId>;
+    fn author(&self) -> &User;
+    fn member_roles(&self) -> Option<&Vec<RoleId>>;
+    fn content(&self) -> &str;
+    async fn say(&self, ctx: &Context, content: String) -> serenity::Result<()>;
+    async fn say_embed(
+        &self,
+        ctx: &Context,
+        title: String,
+        description: String,
+        footer: Option<String>,
+    ) -> serenity::Result<()>;
+}
+
+#[async_trait]
+impl CommandSource for Message {
+    fn guild_id(&self) -> Option<GuildId> {
+        self.guild_id
+    }
+
+    fn author(&self) -> &User {
+        &self.author
+    }
+
+    fn member_roles(&self) -> Option<&Vec<RoleId>> {
+        self.member.as_ref().map(|m| &m.roles)
+    }
+
+    fn content(&self) -> &str {
+        &self.content
+    }
+
+    async fn say(&self, ctx: &Context, content: String) -> serenity::Result<()> {
+        self.channel_id.say(&ctx.http, content).await.map(|_| ())
+    }
+
+    async fn say_embed(
+        &self,
+        ctx: &Context,
+        title: String,
+        description: String,
+        footer: Option<String>,
+    ) -> serenity::Result<()> {
+        self.channel_id
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    e.title(title);
+                    e.description(description);
+                    if let Some(footer) = footer {
+                        e.footer(|f| f.text(footer));
+                    }
+                    e
+                });
+                m
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+// Lets commands pass on "&msg" when they already have a reference
+#[async_trait]
+impl<T: CommandSource> CommandSource for &T {
+    fn guild_id(&self) -> Option<GuildId> {
+        (**self).guild_id()
+    }
+
+    fn author(&self) -> &User {
+        (**self).author()
+    }
+
+    fn member_roles(&self) -> Option<&Vec<RoleId>> {
+        (**self).member_roles()
+    }
+
+    fn content(&self) -> &str {
+        (**self).content()
+    }
+
+    async fn say(&self, ctx: &Context, content: String) -> serenity::Result<()> {
+        (**self).say(ctx, content).await
+    }
+
+    async fn say_embed(
+        &self,
+        ctx: &Context,
+        title: String,
+        description: String,
+        footer: Option<String>,
+    ) -> serenity::Result<()> {
+        (**self).say_embed(ctx, title, description, footer).await
+    }
+}
+
 // Function to load game from CSV file
 
 async fn load_game(game_name: String, path: &str) -> GameType {
     let mut rdr = Reader::from_path(path).unwrap();
     let mut temp_hashmap: HashMap<String, Character> = HashMap::new();
+    let mut aliases: HashMap<String, String> = HashMap::new();
 
     for result in rdr.records() {
         let record = result.unwrap();
@@ -218,19 +430,130 @@ async fn load_game(game_name: String, path: &str) -> GameType {
             _ => night_action = ActionTime::NoNight,
         }
 
-        temp_hashmap.insert(
-            String::from(&name),
-            Character::new(
-                name,
-                char_type,
-                first_order_index,
-                order_index,
-                night_action,
-            ),
+        let ability = String::from(record.get(5).unwrap_or(""));
+
+        // Reminder tokens are stored as a semicolon separated list, with a * on
+        // the ones that only last the night they're placed, like "Safe*"
+        let mut reminders: Vec<String> = Vec::new();
+        let mut night_reminders: Vec<String> = Vec::new();
+
+        for reminder in record
+            .get(6)
+            .unwrap_or("")
+            .split(';')
+            .filter(|r| !r.is_empty())
+        {
+            match reminder.strip_suffix('*') {
+                Some(text) => {
+                    reminders.push(String::from(text));
+                    night_reminders.push(String::from(text));
+                }
+                None => reminders.push(String::from(reminder)),
+            }
+        }
+
+        // Aliases are short names storytellers type, like "ft" for Fortune Teller
+        for alias in record.get(7).unwrap_or("").split(';').filter(|a| !a.is_empty()) {
+            aliases.insert(String::from(alias), name.clone());
+        }
+
+        let mut character = Character::new(
+            name.clone(),
+            char_type,
+            first_order_index,
+            order_index,
+            night_action,
+            ability,
+            reminders,
         );
+
+        character.night_reminders = night_reminders;
+
+        temp_hashmap.insert(name, character);
+    }
+
+    GameType::new(game_name, temp_hashmap, aliases)
+}
+
+const JINXES_PATH: &str = "jinxes/Jinxes.csv";
+
+// Jinxes are shared by every script, each game keeps the ones it can have
+fn load_jinxes() -> Vec<Jinx> {
+    let mut rdr = match Reader::from_path(JINXES_PATH) {
+        Ok(value) => value,
+        Err(_) => return Vec::new(),
+    };
+
+    rdr.records()
+        .flatten()
+        .filter_map(|record| {
+            Some(Jinx {
+                first: String::from(record.get(0)?),
+                second: String::from(record.get(1)?),
+                rule: String::from(record.get(2)?),
+            })
+        })
+        .collect()
+}
+
+// Functions to keep players' original nicknames on disk, so they can be
+// given back even if the bot is restarted in the middle of a game
+
+const NICKNAMES_PATH: &str = "nicknames.csv";
+
+fn load_original_nicks() -> Vec<(u64, u64, Option<String>)> {
+    let mut saved = Vec::new();
+
+    let mut rdr = match Reader::from_path(NICKNAMES_PATH) {
+        Ok(value) => value,
+        Err(_) => return saved,
+    };
+
+    for record in rdr.records().flatten() {
+        let guild_id = record.get(0).and_then(|g| g.parse::<u64>().ok());
+        let user_id = record.get(1).and_then(|u| u.parse::<u64>().ok());
+        let nick = record.get(2).filter(|n| !n.is_empty()).map(String::from);
+
+        if let (Some(guild_id), Some(user_id)) = (guild_id, user_id) {
+            saved.push((guild_id, user_id, nick));
+        }
+    }
+
+    saved
+}
+
+fn save_original_nicks(guild_id: u64, nicks: &HashMap<u64, Option<String>>) {
+    // Keep everything saved by other servers
+    let mut saved: Vec<(u64, u64, Option<String>)> = load_original_nicks()
+        .into_iter()
+        .filter(|n| n.0 != guild_id)
+        .collect();
+
+    for (user_id, nick) in nicks {
+        saved.push((guild_id, *user_id, nick.clone()));
+    }
+
+    let mut wtr = match Writer::from_path(NICKNAMES_PATH) {
+        Ok(value) => value,
+        Err(why) => {
+            print_error(&format!("Could not save nicknames: {:?}", why));
+            return;
+        }
+    };
+
+    let _ = wtr.write_record(["Guild ID", "User ID", "Nickname"]);
+
+    for (guild_id, user_id, nick) in saved {
+        let _ = wtr.write_record([
+            guild_id.to_string(),
+            user_id.to_string(),
+            nick.unwrap_or_default(),
+        ]);
     }
 
-    return GameType::new(game_name, temp_hashmap);
+    if let Err(why) = wtr.flush() {
+        print_error(&format!("Could not save nicknames: {:?}", why));
+    }
 }
 
 // Here are the custom enums and structs for each server
@@ -246,6 +569,17 @@ pub enum GameState {
     Playing,
 }
 
+impl GameState {
+    pub fn as_str(&self) -> &str {
+        match *self {
+            GameState::Nothing => "No game",
+            GameState::SettingUp => "Setting up",
+            GameState::SettingRoles => "Assigning roles",
+            GameState::Playing => "Playing",
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum Time {
     Day,
@@ -261,6 +595,24 @@ impl Time {
     }
 }
 
+// What to do when a player leaves their night room
+#[derive(Copy, Clone)]
+pub enum WatchPolicy {
+    Off,
+    Warn,
+    Return,
+}
+
+impl WatchPolicy {
+    pub fn as_str(&self) -> &str {
+        match *self {
+            WatchPolicy::Off => "Off",
+            WatchPolicy::Warn => "Warn the Storyteller",
+            WatchPolicy::Return => "Return them to their room",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct BloodGuild {
     id: u64,
@@ -270,6 +622,57 @@ pub struct BloodGuild {
     game_type: GameType,
     time: Time,
     day_index: u32,
+    night_rooms: HashMap<u64, u64>,
+    night_step: usize,
+    town_channel: Option<u64>,
+    night_watch: WatchPolicy,
+    day_watch: bool,
+    locked_channels: Vec<u64>,
+    saved_overwrites: HashMap<u64, Vec<PermissionOverwrite>>,
+    night_mute: bool,
+    muted: HashSet<u64>,
+    dead: HashSet<u64>,
+    used_ghost_votes: HashSet<u64>,
+    nicknames: bool,
+    original_nicks: HashMap<u64, Option<String>>,
+    control_panel: Option<u64>,
+    role_menus: HashMap<u64, u64>,
+    fabled: Vec<Character>,
+    starting_evil: usize,
+    fiddler_used: bool,
+    tokens: HashMap<u64, Vec<ReminderToken>>,
+    notes: HashMap<u64, String>,
+    public_channel: Option<u64>,
+    status_board: Option<u64>,
+    night_deaths: Vec<u64>,
+    dawn_pending: bool,
+    choice_prompts: ChoiceTiming,
+    night_choices: HashMap<u64, Vec<Option<String>>>,
+    info_log: HashMap<u64, Vec<InfoEntry>>,
+    whispers: Vec<Whisper>,
+    timers: DayTimers,
+}
+
+// One wake-up in the night order, either a player or a Fabled the storyteller runs
+#[derive(Clone)]
+pub struct NightStep {
+    pub player: Option<(u64, Member)>,
+    pub character: Character,
+}
+
+impl NightStep {
+    fn is_same(&self, other: &NightStep) -> bool {
+        self.player.as_ref().map(|p| p.0) == other.player.as_ref().map(|p| p.0)
+            && self.character.name == other.character.name
+    }
+}
+
+// Which way "next", "back" and "skip" move through the night order
+#[derive(PartialEq)]
+enum Walk {
+    Next,
+    Back,
+    Skip,
 }
 
 // Global HashMap struct to hold all global data
@@ -277,6 +680,9 @@ pub struct BloodGuild {
 pub struct GlobalBloodState {
     blood_guilds: HashMap<u64, BloodGuild>,
     games: Vec<GameType>,
+    fabled: GameType,
+    // Players still muted by a game that has ended, by guild, as they weren't in voice to unmute
+    left_muted: HashMap<u64, HashSet<u64>>,
 }
 
 impl BloodGuild {
@@ -290,12 +696,348 @@ impl BloodGuild {
             time: Time::Day,
             day_index: 0,
             game_type: game_type,
+            night_rooms: HashMap::new(),
+            night_step: 0,
+            town_channel: None,
+            night_watch: WatchPolicy::Warn,
+            day_watch: true,
+            locked_channels: Vec::new(),
+            saved_overwrites: HashMap::new(),
+            night_mute: false,
+            muted: HashSet::new(),
+            dead: HashSet::new(),
+            used_ghost_votes: HashSet::new(),
+            nicknames: false,
+            original_nicks: HashMap::new(),
+            control_panel: None,
+            role_menus: HashMap::new(),
+            fabled: Vec::new(),
+            starting_evil: 0,
+            fiddler_used: false,
+            tokens: HashMap::new(),
+            notes: HashMap::new(),
+            public_channel: None,
+            status_board: None,
+            night_deaths: Vec::new(),
+            dawn_pending: false,
+            choice_prompts: ChoiceTiming::Off,
+            night_choices: HashMap::new(),
+            info_log: HashMap::new(),
+            whispers: Vec::new(),
+            timers: DayTimers::new(),
         }
     }
 
     pub fn get_time_str(&self) -> String {
         return format!("Day: {} | Time: {}", self.day_index, self.time.as_str());
     }
+
+    /// Finds a seat (counted from 0) from either a seat number or a mention
+    pub fn find_seat(&self, param: &str) -> Option<usize> {
+        if let Ok(num) = param.parse::<usize>() {
+            if num > 0 && num <= self.roles.len() {
+                return Some(num - 1);
+            }
+
+            return None;
+        }
+
+        let user_id = serenity::utils::parse_username(param)?;
+
+        self.roles.iter().position(|p| p.0 == user_id)
+    }
+
+    pub fn is_alive(&self, user_id: u64) -> bool {
+        !self.dead.contains(&user_id)
+    }
+
+    /// Whether everyone still thinks this player is alive, as deaths in the
+    /// night are kept quiet until they're announced at dawn
+    pub fn is_publicly_alive(&self, user_id: u64) -> bool {
+        self.is_alive(user_id) || self.night_deaths.contains(&user_id)
+    }
+
+    /// The nickname a player should have right now, like "3. 💀 Name"
+    pub fn get_decorated_nick(&self, seat: usize) -> String {
+        let player = &self.roles[seat];
+
+        let name = match self.original_nicks.get(&player.0) {
+            Some(Some(value)) => value.clone(),
+            _ => player.1.user.name.clone(),
+        };
+
+        let mut markers = String::new();
+
+        if !self.is_publicly_alive(player.0) {
+            markers.push('💀');
+
+            if self.used_ghost_votes.contains(&player.0) {
+                markers.push('✖');
+            }
+
+            markers.push(' ');
+        }
+
+        let nick = format!("{}. {}{}", seat + 1, markers, name);
+
+        // Discord won't accept nicknames longer than 32 characters
+        nick.chars().take(32).collect()
+    }
+
+    /// Returns every player who wakes tonight, in the order they wake
+    pub fn get_night_order(&self) -> Vec<NightStep> {
+        let mut order: Vec<NightStep> = Vec::new();
+
+        let wakes = |character: &Character| {
+            if self.day_index == 1 {
+                character.first_order_index != -1
+            } else {
+                character.order_index != -1
+            }
+        };
+
+        for character in &self.fabled {
+            if wakes(character) {
+                order.push(NightStep {
+                    player: None,
+                    character: character.clone(),
+                });
+            }
+        }
+
+        for player in &self.roles {
+            if let Some(character) = &player.2 {
+                // The dead don't wake, unless dying tonight is what wakes them
+                let awake = self.is_alive(player.0)
+                    || (matches!(character.night_action, ActionTime::DeathNight)
+                        && self.night_deaths.contains(&player.0));
+
+                if awake && wakes(character) {
+                    order.push(NightStep {
+                        player: Some((player.0, player.1.clone())),
+                        character: character.clone(),
+                    });
+                }
+            }
+        }
+
+        if self.day_index == 1 {
+            order.sort_by_key(|d| d.character.first_order_index);
+        } else {
+            order.sort_by_key(|d| d.character.order_index);
+        }
+
+        order
+    }
+
+    /// Keeps the walkthrough on the same player when a death or revival changes tonight's order
+    pub fn keep_night_step(&mut self, before: &[NightStep]) {
+        let order = self.get_night_order();
+
+        if self.night_step > before.len() {
+            self.night_step = order.len() + 1;
+            return;
+        }
+
+        let done = &before[..self.night_step];
+
+        self.night_step = order
+            .iter()
+            .filter(|step| done.iter().any(|d| d.is_same(step)))
+            .count();
+    }
+
+    /// One line of the night order, like "**Alice** as the Monk"
+    pub fn get_night_step_line(&self, step: &NightStep) -> String {
+        let warning = match (self.day_index, &step.character.night_action) {
+            (1, _) => "",
+            (_, ActionTime::OnlyFirstNight)
+            | (_, ActionTime::VariableNight)
+            | (_, ActionTime::DeathNight) => " *if triggered*",
+            _ => "",
+        };
+
+        match &step.player {
+            Some((user_id, member)) => {
+                let tokens = match get_tokens_str(self, *user_id) {
+                    Some(value) => format!(" [{}]", value),
+                    None => String::new(),
+                };
+
+                format!(
+                    "**{}** as the {}{}{}",
+                    get_member_name(member),
+                    step.character.name,
+                    warning,
+                    tokens
+                )
+            }
+            None => format!("The **{}** (Fabled){}", step.character.name, warning),
+        }
+    }
+
+    /// Jinxes on this character whose other character is also in play
+    pub fn get_active_jinxes(&self, character_name: &str) -> Vec<(String, String)> {
+        let in_play: Vec<&str> = self
+            .roles
+            .iter()
+            .filter_map(|p| p.2.as_ref())
+            .map(|c| c.name.as_str())
+            .chain(self.fabled.iter().map(|f| f.name.as_str()))
+            .collect();
+
+        self.game_type
+            .get_jinxes()
+            .iter()
+            .filter_map(|j| {
+                let other = j.other(character_name)?;
+
+                if in_play.contains(&other) {
+                    Some((String::from(other), j.rule.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The whole night order as a numbered list
+    pub fn get_night_order_text(&self) -> String {
+        let mut content = String::new();
+
+        for (index, step) in self.get_night_order().iter().enumerate() {
+            content.push_str(&format!("{}) {}\n", index + 1, self.get_night_step_line(step)));
+        }
+
+        content
+    }
+
+    pub fn has_fabled(&self, name: &str) -> bool {
+        self.fabled.iter().any(|f| f.name == name)
+    }
+
+    /// Evil players, including evil Travelers
+    pub fn count_evil(&self) -> usize {
+        count_evil_characters(self.roles.iter().filter_map(|p| p.2.as_ref()))
+    }
+
+    /// Whether the Spirit of Ivory is in play and there's more than 1 extra evil player
+    pub fn breaks_spirit_of_ivory(&self) -> bool {
+        self.has_fabled("Spirit of Ivory")
+            && matches!(self.game_state, GameState::Playing)
+            && self.count_evil() > self.starting_evil + 1
+    }
+
+    /// Works out the information the Storyteller would most likely give
+    /// this player tonight, if their character has a deterministic answer
+    pub fn get_suggested_info(&self, user_id: u64) -> Option<String> {
+        let seat = self.roles.iter().position(|p| p.0 == user_id)?;
+        let character = self.roles[seat].2.as_ref()?;
+        let total = self.roles.len();
+
+        let is_evil = |index: usize| match &self.roles[index].2 {
+            Some(c) => matches!(c.alignment, Alignment::Evil),
+            None => false,
+        };
+
+        let is_type = |index: usize, char_type: &str| match &self.roles[index].2 {
+            Some(c) => c.char_type_str == char_type,
+            None => false,
+        };
+
+        let name_of = |index: usize| get_member_name(&self.roles[index].1);
+
+        match character.name.as_str() {
+            "Chef" => {
+                let mut pairs = 0;
+
+                for index in 0..total {
+                    if is_evil(index) && is_evil((index + 1) % total) {
+                        pairs += 1;
+                    }
+                }
+
+                // A circle of two evil players would otherwise count the same pair twice
+                if total == 2 && pairs == 2 {
+                    pairs = 1;
+                }
+
+                Some(format!("Show **{}** (pairs of evil players)", pairs))
+            }
+            "Empath" => {
+                let is_alive_seat = |index: usize| self.is_alive(self.roles[index].0);
+
+                // Dead players are skipped over when finding neighbours
+                let left = (1..total)
+                    .map(|step| (seat + total - step) % total)
+                    .find(|i| is_alive_seat(*i))?;
+                let right = (1..total)
+                    .map(|step| (seat + step) % total)
+                    .find(|i| is_alive_seat(*i))?;
+
+                let mut count = 0;
+
+                if is_evil(left) {
+                    count += 1;
+                }
+                if right != left && is_evil(right) {
+                    count += 1;
+                }
+
+                Some(format!(
+                    "Show **{}** ({} and {} are their neighbours)",
+                    count,
+                    name_of(left),
+                    name_of(right)
+                ))
+            }
+            "Clockmaker" => {
+                let demon = (0..total).find(|i| is_type(*i, "Demon"))?;
+
+                let mut steps: Option<usize> = None;
+
+                for index in 0..total {
+                    if is_type(index, "Minion") {
+                        let distance = (index + total - demon) % total;
+                        let distance = distance.min(total - distance);
+
+                        if steps.is_none() || distance < steps.unwrap() {
+                            steps = Some(distance);
+                        }
+                    }
+                }
+
+                Some(format!("Show **{}** (steps from the Demon to its nearest Minion)", steps?))
+            }
+            "Washerwoman" | "Librarian" | "Investigator" => {
+                let target_type = match character.name.as_str() {
+                    "Washerwoman" => "Townsfolk",
+                    "Librarian" => "Outsider",
+                    _ => "Minion",
+                };
+
+                let target = (0..total).find(|i| *i != seat && is_type(*i, target_type));
+
+                if let Some(target) = target {
+                    // Pair the real target with their neighbour who isn't the player
+                    let mut other = (target + 1) % total;
+                    if other == seat {
+                        other = (other + 1) % total;
+                    }
+
+                    Some(format!(
+                        "Show **{}** and **{}**, and the **{}** token",
+                        name_of(target),
+                        name_of(other),
+                        self.roles[target].2.as_ref().unwrap().name
+                    ))
+                } else {
+                    Some(format!("Show **0** (no {} in play)", target_type))
+                }
+            }
+            _ => None,
+        }
+    }
 }
 
 impl GlobalBloodState {
@@ -304,6 +1046,8 @@ impl GlobalBloodState {
         GlobalBloodState {
             blood_guilds: HashMap::new(),
             games: Vec::new(),
+            fabled: GameType::new(String::from("Fabled"), HashMap::new(), HashMap::new()),
+            left_muted: HashMap::new(),
         }
     }
 }
@@ -336,28 +1080,50 @@ async fn main() {
     let token = env::var("BLOOD_TOKEN")
     .expect("Please set your BLOOD_TOKEN! Follow instructions at https://github.com/IonImpulse/blood-on-the-clocktower-discord-bot!");
 
-    let mut client = Client::builder(token)
+    let mut builder = Client::builder(&token)
         .event_handler(Handler)
-        .framework(framework)
+        .framework(framework);
+
+    // Slash commands need to know which application they belong to, but the
+    // bot still works without them
+    match Http::new_with_token(&token)
+        .get_current_application_info()
         .await
-        .expect("Error creating client");
+    {
+        Ok(value) => builder = builder.application_id(*value.id.as_u64()),
+        Err(why) => print_error(&format!(
+            "Could not get application info, so slash commands are off: {:?}",
+            why
+        )),
+    }
+
+    let mut client = builder.await.expect("Error creating client");
 
     print_status("Loading games...");
 
     // Loop through 'games' folder and load all games
     // where the name is the filename without .csv
 
+    let jinxes = load_jinxes();
+
     for entry in std::fs::read_dir("games").unwrap() {
         let entry = entry.unwrap();
         let path = entry.path();
         let file_name = path.file_name().unwrap().to_str().unwrap();
         let game_name = file_name.split('.').collect::<Vec<&str>>()[0];
-        let game_type = load_game(String::from(game_name), path.to_str().unwrap()).await;
+        let mut game_type = load_game(String::from(game_name), path.to_str().unwrap()).await;
+
+        game_type.set_jinxes(&jinxes);
 
         // Lock and unlock the database to add the game
         BLOOD_DATABASE.lock().await.games.push(game_type);
     }
 
+    // Fabled aren't part of any script, so they live on their own
+    if std::path::Path::new(FABLED_PATH).exists() {
+        BLOOD_DATABASE.lock().await.fabled = load_game(String::from("Fabled"), FABLED_PATH).await;
+    }
+
     print_info("Loaded games:");
     for game in &BLOOD_DATABASE.lock().await.games {
         print_info(&format!(" - {}", game.get_name()));
@@ -505,43 +1271,9 @@ async fn start(ctx: &Context, msg: &Message) -> CommandResult {
             let (idx, _) =
                 reaction_prompt(ctx, &prompt_msg.unwrap(), &msg.author, &emojis, 120.0).await?;
 
-            let content = String::from(
-                "**Type \"roles\" to start assigning roles once everyone is in voice chat!**",
-            );
-
-            send_msg(&msg, &ctx, content).await;
-
-            // Start accesssing main database with lock
-            let mut lock = BLOOD_DATABASE.lock().await;
-
-            let game_type = lock.games.get(idx).unwrap().clone();
-
-            let temp_server = BloodGuild::new(*guild_id, *channel_id, game_type.clone());
-
-            lock.blood_guilds.insert(*guild_id, temp_server);
-
-            let num_servers = lock.blood_guilds.len();
-
-            drop(lock);
-            // Unlock main database
+            let game_type = BLOOD_DATABASE.lock().await.games.get(idx).unwrap().clone();
 
-            let mut content: String = String::from(
-                "```markdown\n       Name       | Character Type |      Wake Condition      \n",
-            );
-            content += "--------------------------------------------------------------\n";
-
-            let mut characters = game_type.get_all_characters();
-            characters.sort_by_key(|d| d.char_type_str.clone());
-
-            for character in characters {
-                content += character.get_string().as_str();
-                content += "\n";
-            }
-            content += "```";
-
-            send_msg(&msg, &ctx, content).await;
-
-            print_info(&format!("There are {} active games", num_servers));
+            create_game(ctx, msg, *guild_id, *channel_id, game_type).await;
         } else {
             print_error("Could not retrieve Guild ID (Command from a DM?)");
         }
@@ -560,20 +1292,7 @@ async fn end(ctx: &Context, msg: &Message) -> CommandResult {
         if is_guild {
             let guild_id = msg.guild_id.as_ref().unwrap().as_u64();
 
-            let content = String::from("**Ended game!**");
-            send_msg(&msg, &ctx, content).await;
-
-            // Start accesssing main database with lock
-            let mut lock = BLOOD_DATABASE.lock().await;
-
-            lock.blood_guilds.remove(&guild_id);
-
-            let num_servers = lock.blood_guilds.len();
-
-            drop(lock);
-            // Unlock main database
-
-            print_info(&format!("There are {} active games", num_servers));
+            end_game(ctx, msg, *guild_id).await;
         } else {
             print_error("Could not retrieve Guild ID (Command from a DM?)");
         }
@@ -582,21 +1301,207 @@ async fn end(ctx: &Context, msg: &Message) -> CommandResult {
     Ok(())
 }
 
-#[command]
-async fn help(ctx: &Context, msg: &Message) -> CommandResult {
-    // Return a message with all the commands
-    print_command(&msg);
-
-    let help_msg = "```markdown
-Use the command ~start to start a game.
+async fn create_game(
+    ctx: &Context,
+    msg: &impl CommandSource,
+    guild_id: u64,
+    channel_id: u64,
+    game_type: GameType,
+) {
+    let content = String::from(
+        "**Type \"roles\" to start assigning roles once everyone is in voice chat!**",
+    );
 
-Blood 🩸 will be bound to the channel that ~start was executed in, so for the duration of the game, you won't need to use the ~ prefix to execute commands.
-Ex: Someone with a \"Storytellers\" role sends the message \"~start\" in a channel that only storytellers can see. Blood will respond with a confirmation, and will now respond to commands in that channel without needing to use the prefix ~.
+    send_msg(msg, ctx, content).await;
+
+    // Start accesssing main database with lock
+    let mut lock = BLOOD_DATABASE.lock().await;
+
+    let temp_server = BloodGuild::new(guild_id, channel_id, game_type.clone());
+
+    lock.blood_guilds.insert(guild_id, temp_server);
+
+    let num_servers = lock.blood_guilds.len();
+
+    drop(lock);
+    // Unlock main database
+
+    let mut content: String = String::from(
+        "```markdown\n       Name       | Character Type |      Wake Condition      \n",
+    );
+    content += "--------------------------------------------------------------\n";
+
+    let mut characters = game_type.get_all_characters();
+    characters.sort_by_key(|d| d.char_type_str.clone());
+
+    for character in characters {
+        content += character.get_string().as_str();
+        content += "\n";
+    }
+    content += "```";
+
+    send_msg(msg, ctx, content).await;
+
+    if !game_type.get_script_jinxes().is_empty() {
+        let mut jinx_list = String::from("**Jinxes on this script:**\n");
+
+        for jinx in game_type.get_script_jinxes() {
+            jinx_list += &format!("**{}** & **{}**: {}\n", jinx.first, jinx.second, jinx.rule);
+        }
+
+        send_msg(msg, ctx, jinx_list).await;
+    }
+
+    send_msg(msg, ctx, get_fabled_summary().await).await;
+
+    post_control_panel(ctx, guild_id).await;
+
+    print_info(&format!("There are {} active games", num_servers));
+}
+
+async fn end_game(ctx: &Context, msg: &impl CommandSource, guild_id: u64) {
+    let content = String::from("**Ended game!**");
+    send_msg(msg, ctx, content).await;
+
+    // Start accesssing main database with lock
+    let mut lock = BLOOD_DATABASE.lock().await;
+
+    let removed = lock.blood_guilds.remove(&guild_id);
+
+    let num_servers = lock.blood_guilds.len();
+
+    drop(lock);
+    // Unlock main database
+
+    // Don't leave anyone locked out, muted or renamed when the game ends
+    if let Some(mut old_state) = removed {
+        lift_night_lockdown(ctx, &mut old_state).await;
+
+        // Whoever couldn't be unmuted yet is unmuted when they next join voice
+        if !old_state.muted.is_empty() {
+            BLOOD_DATABASE
+                .lock()
+                .await
+                .left_muted
+                .entry(guild_id)
+                .or_default()
+                .extend(old_state.muted.drain());
+        }
+
+        restore_nicknames(ctx, &mut old_state).await;
+        close_control_panel(ctx, &old_state).await;
+        close_status_board(ctx, &old_state).await;
+        end_whispers(ctx, &mut old_state).await;
+    }
+
+    print_info(&format!("There are {} active games", num_servers));
+}
+
+#[command]
+async fn help(ctx: &Context, msg: &Message) -> CommandResult {
+    // Return a message with all the commands
+    print_command(&msg);
+
+    let help_msg = "```markdown
+Use the command ~start to start a game.
+
+Blood 🩸 will be bound to the channel that ~start was executed in, so for the duration of the game, you won't need to use the ~ prefix to execute commands.
+Ex: Someone with a \"Storytellers\" role sends the message \"~start\" in a channel that only storytellers can see. Blood will respond with a confirmation, and will now respond to commands in that channel without needing to use the prefix ~.
 
 Now you can send the following commands without a prefix in that channel to continue the flow of the game:
 roles
 
-    Starts a call/response to save the role of every player in the Voice Channel who is not the storyteller. Once done, you can type dm and start the game!.
+    Collects every player in the Voice Channel who is not the storyteller and posts a message for each with menus of the script's characters. Picking a Drunk or Lunatic brings up menus for who they believe they are. You can still type roles in seat order instead, using full names, short names like ft or sw, or the start of a name. If several characters match, you'll be asked which one you meant. Once done, you can type dm and start the game!.
+
+assign [roles]
+
+    Assigns every role in one message, either in seat order like \"assign imp, chef, empath\" or with a \"@player: role\" line each. Write \"lunatic (imp)\" for who a Drunk or Lunatic believes they are. Type roles first to collect the players.
+
+add [@player] [role]
+
+    Adds someone in the next seat. Once the game has started, give their role too and they'll be DMed it.
+
+remove [seat]
+
+    Takes a player out of the game by seat number or mention. Everyone after them moves up a seat.
+
+replace [seat] [@player]
+
+    Swaps a new member into a seat, keeping its character, night room and life. They're DMed the role if the game has started.
+
+traveler [@player] [character] [good/evil]
+
+    Adds a Traveler in the next seat with the alignment you choose. They're DMed it if the game has started.
+
+exile [seat] [votes]
+
+    Calls a Traveler for exile and says how many votes are needed. Add the number of votes to see if they're exiled.
+
+fabled [add/remove] [name]
+
+    Adds or removes a Fabled, or shows which are in play. Fabled that wake at night get their own night order steps.
+
+fiddler [demon seat] [chosen seat] [votes] [votes]
+
+    Runs the Fiddler's vote. Add the votes for each player to see which team wins.
+
+become [seat] [character]
+
+    Changes a player's character mid-game, keeping their alignment, and DMs them.
+
+swap [seat] [seat] [alignments]
+
+    Swaps two players' characters (and alignments, if you add \"alignments\") and DMs them.
+
+align [seat] [good/evil]
+
+    Changes a player's alignment and DMs them.
+
+token [add/remove] [seat] [token]
+
+    Places or removes a reminder token from a character in play, or lists every token. Night-only tokens are removed at day.
+
+grim
+
+    Shows every seat with their character, alignment, life, ghost vote, tokens and notes.
+
+grim image
+grim public
+
+    Draws the grimoire as a picture for you, or the town square without hidden characters for the public channel.
+
+public [#channel]
+
+    Sets the text channel public information is posted in, and keeps a status board there with the seats, deaths, ghost votes, Travelers and Fabled.
+
+dawn
+dawn hold
+
+    Announces who died last night in the public channel, or keeps the announcement back for now.
+
+choices [off/dusk/step]
+
+    DMs players a menu for their night choices, either all at night or as the walkthrough reaches them.
+
+tell [seat] [text]
+
+    DMs a player their information and records it. Use a single answer like \"tell 3 2\" for their character's template, or \"tell 3 chef: 1\" for a named one.
+
+whisper [seat] [seat]
+whisper end
+
+    Makes a private voice channel for two players and announces the whisper. They're deleted at night. Players can DM \"whisper [player]\" too.
+
+timer [discussion/nominations/speech] [length]
+timer set [timer] [length]
+timer stop
+timer auto [on/off]
+
+    Counts down part of the day in the public channel, warning you before it ends. With auto on, it becomes night when nominations run out.
+
+note [seat] [text]
+
+    Keeps a note on a player for the grimoire, or clears it if there's no text.
 
 edit [number]
 
@@ -610,6 +1515,45 @@ night
 
     Moves everyone but the storyteller who executed the command (who is also in the Voice Channel the storyteller is in) to a night room. If there is a saved ordering of people, it will use that order.
 
+next
+
+    Walks through the night order one step at a time. Moves you into the night room of the player who wakes next, and shows their ability, reminders and suggested info.
+
+back
+
+    Goes back to the previous step of the night order.
+
+skip
+
+    Skips over the next step of the night order without waking that player.
+
+watch night [off/warn/return]
+watch day [on/off]
+
+    Sets what happens when a player leaves their night room at night, or leaves the town square during the day. Type watch on its own to see the current settings.
+
+lock add [#channel]
+lock remove [#channel]
+
+    Sets which public text channels players can't send messages in at night. Everything is put back the way it was at day.
+
+nightmute [on/off]
+
+    When on, players who leave their own night room are server muted until they go back or it's day.
+
+kill [number]
+revive [number]
+
+    Marks the player in that seat as dead or alive again.
+
+vote [number]
+
+    Marks a dead player's ghost vote as used. Use it again to give it back.
+
+nicknames [on/off]
+
+    When on, players' nicknames show their seat number, a skull once they're dead, and a cross once their ghost vote is used. Original nicknames are put back at ~end.
+
 day
 
     Saves the ordering of people in night rooms and moves everyone to the Voice Channel with \"town\" in the name.
@@ -618,6 +1562,15 @@ save
 
     Saves the ordering of people in night rooms without moving them.
 
+Slash commands:
+/start, /end, /roles, /edit, /dm, /night and /day do the same as the commands above, but only you can see Blood's replies so the channel stays readable. /edit lets you pick a player and their character directly.
+
+Control panel:
+When a game starts, a panel with Night, Day, DM roles, Edit and End buttons is posted in the storyteller channel. It shows the phase, time and living players, and updates itself after every change.
+
+Player DMs:
+Players can DM the bot \"role\", \"script\", \"ability [character]\", \"status\" or \"info\" to see their character, the script, a character's ability, who's alive or what they've been told, or \"whisper [player]\" to start a whisper.
+
 Then, use the command ~end to end the game. This will clear all data, including which channel is bound, which roles players have, and how many nights have passed.
 
 Questions/Comments:
@@ -632,10 +1585,10 @@ https://raw.githubusercontent.com/IonImpulse/blood-on-the-clocktower-discord-bot
     Ok(())
 }
 
-async fn roles(ctx: &Context, msg: &Message) {
+async fn roles(ctx: &Context, msg: &impl CommandSource) {
     print_command(&msg);
 
-    let guild_id = msg.guild_id.as_ref().unwrap().as_u64();
+    let guild_id = &msg.guild_id().unwrap().0;
 
     let mut current_state = get_database(&guild_id).await;
 
@@ -654,7 +1607,7 @@ async fn roles(ctx: &Context, msg: &Message) {
         current_state.game_state = GameState::SettingRoles;
 
         // Check to see if this was called for the first time or is a continuation
-        if &msg.content == "roles" {
+        if msg.content() == "roles" {
             // If it hasn't, get all channels, check each one to see if it's
             // a voice channel, and if it is a voice channel, see if the
             // storyteller who sent the command is in it. If something fails,
@@ -666,7 +1619,7 @@ async fn roles(ctx: &Context, msg: &Message) {
 
             let mut storyteller_voice_channel: Option<GuildChannel> = None;
 
-            let storyteller_id = msg.author.id;
+            let storyteller_id = msg.author().id;
 
             for channel in all_channels {
                 if channel.1.kind == ChannelType::Voice {
@@ -706,7 +1659,9 @@ async fn roles(ctx: &Context, msg: &Message) {
                     send_msg(
                         &msg,
                         &ctx,
-                        String::from("**Done!** Please respond to the prompts for each member:"),
+                        String::from(
+                            "**Done!** Pick a role for each member below, or type them in order:",
+                        ),
                     )
                     .await;
 
@@ -769,27 +1724,33 @@ async fn roles(ctx: &Context, msg: &Message) {
                 if temp_char_type == "Decoy" || temp_char_type == "Normal" {
                     let mut found_character: Option<Character> = None;
 
-                    for character in current_state.game_type.get_all_characters() {
-                        if character
-                            .name
-                            .to_lowercase()
-                            .contains(msg.content.clone().to_lowercase().as_str())
-                        {
-                            found_character = Some(character.clone());
+                    match current_state.game_type.find_character(msg.content()) {
+                        CharacterMatch::Found(character) => found_character = Some(character),
+                        CharacterMatch::Ambiguous(choices) => {
+                            // Let the storyteller pick instead of guessing
+                            let user_id = current_state.roles[index].0;
+
+                            ask_to_pick_role(
+                                ctx,
+                                &current_state,
+                                user_id,
+                                temp_char_type == "Decoy",
+                                choices,
+                            )
+                            .await;
+
+                            set_database(current_state).await;
+
                             break;
                         }
+                        CharacterMatch::NotFound => {}
                     }
 
                     if let Some(c_value) = found_character.clone() {
                         if temp_char_type == "Decoy" {
                             let real_char = current_state.roles[index.clone()].clone().2.unwrap();
 
-                            let decoy_char: DecoyCharacter = DecoyCharacter {
-                                name: c_value.name,
-                                alignment: c_value.alignment,
-                                char_type: c_value.char_type,
-                                char_type_str: c_value.char_type_str,
-                            };
+                            let decoy_char: DecoyCharacter = c_value.to_decoy();
 
                             let char_to_assign: Character =
                                 Character::add_decoy(real_char, decoy_char);
@@ -816,10 +1777,13 @@ async fn roles(ctx: &Context, msg: &Message) {
                                 current_state.roles[index.clone()].2.as_ref().unwrap().name
                             ));
                         }
+
+                        let user_id = current_state.roles[index].0;
+                        refresh_role_menu(ctx, &current_state, user_id).await;
                     } else {
                         let content = format!(
                             "Could not find role {} in current game. Please try again!",
-                            msg.content.clone().as_str()
+                            msg.content()
                         );
                         send_msg(&msg, &ctx, content).await;
                     }
@@ -835,12 +1799,142 @@ async fn roles(ctx: &Context, msg: &Message) {
     }
 }
 
-async fn dm_roles(ctx: &Context, msg: &Message) {
+async fn assign_roles(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    if let GameState::Playing = current_state.game_state {
+        send_msg(msg, ctx, String::from("Cannot edit roles in-game!")).await;
+        return;
+    }
+
+    if current_state.roles.is_empty() {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** No players yet! Type \"roles\" first to collect them."),
+        )
+        .await;
+        return;
+    }
+
+    let list = msg.content().trim_start().trim_start_matches("assign").trim();
+
+    // Either "@player: role" lines, or a comma separated list in seat order
+    let mut entries: Vec<(Option<usize>, String)> = Vec::new();
+    let mut problems: Vec<String> = Vec::new();
+
+    if list.contains(':') {
+        for line in list.lines().filter(|l| !l.trim().is_empty()) {
+            match line.split_once(':') {
+                Some((player, role)) => match current_state.find_seat(player.trim()) {
+                    Some(seat) => entries.push((Some(seat), String::from(role.trim()))),
+                    None => problems.push(format!("Couldn't find player {}", player.trim())),
+                },
+                None => problems.push(format!("Couldn't read \"{}\"", line.trim())),
+            }
+        }
+    } else {
+        let roles = list.split(',').map(|r| r.trim()).filter(|r| !r.is_empty());
+
+        for (index, role) in roles.enumerate() {
+            if index < current_state.roles.len() {
+                entries.push((Some(index), String::from(role)));
+            } else {
+                entries.push((None, String::from(role)));
+            }
+        }
+    }
+
+    for (seat, role) in entries {
+        let seat = match seat {
+            Some(value) => value,
+            None => {
+                problems.push(format!("There's no seat left for {}", role));
+                continue;
+            }
+        };
+
+        let character = match find_role_entry(&current_state, &role, &mut problems) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        print_info(&format!(
+            "User {} is role {}",
+            current_state.roles[seat].1.user.name, character.name
+        ));
+
+        current_state.roles[seat].2 = Some(character);
+
+        let user_id = current_state.roles[seat].0;
+        refresh_role_menu(ctx, &current_state, user_id).await;
+    }
+
+    if !problems.is_empty() {
+        send_msg(msg, ctx, format!("**Couldn't assign everything:**\n{}", problems.join("\n")))
+            .await;
+    }
+
+    // Anyone left over gets a menu, otherwise this shows the finished role list
+    current_state.game_state = GameState::SettingRoles;
+
+    ask_for_role(ctx, msg, current_state).await;
+}
+
+/// Reads a typed role, where a Drunk or Lunatic can say who they believe
+/// they are in brackets, like "drunk (chef)"
+fn find_role_entry(
+    current_state: &BloodGuild,
+    role: &str,
+    problems: &mut Vec<String>,
+) -> Option<Character> {
+    let (role, believes) = match role.split_once('(') {
+        Some((real, believed)) => (real.trim(), Some(believed.trim_end_matches(')').trim())),
+        None => (role.trim(), None),
+    };
+
+    let character = find_bulk_character(current_state, role, problems)?;
+
+    match believes {
+        Some(believes) => {
+            let believed = find_bulk_character(current_state, believes, problems)?;
+
+            Some(Character::add_decoy(character, believed.to_decoy()))
+        }
+        None => Some(character),
+    }
+}
+
+fn find_bulk_character(
+    current_state: &BloodGuild,
+    input: &str,
+    problems: &mut Vec<String>,
+) -> Option<Character> {
+    match current_state.game_type.find_character(input) {
+        CharacterMatch::Found(character) => Some(character),
+        CharacterMatch::Ambiguous(choices) => {
+            let names: Vec<String> = choices.iter().map(|c| c.name.clone()).collect();
+
+            problems.push(format!("\"{}\" could be {}", input, names.join(" or ")));
+            None
+        }
+        CharacterMatch::NotFound => {
+            problems.push(format!("Could not find role {}", input));
+            None
+        }
+    }
+}
+
+async fn dm_roles(ctx: &Context, msg: &impl CommandSource) {
     print_command(&msg);
 
     send_msg(&msg, &ctx, String::from("**Sending...**")).await;
 
-    let guild_id = msg.guild_id.as_ref().unwrap().as_u64();
+    let guild_id = &msg.guild_id().unwrap().0;
 
     let mut current_state = get_database(&guild_id).await;
 
@@ -848,20 +1942,24 @@ async fn dm_roles(ctx: &Context, msg: &Message) {
         let mut successful_dms: u32 = 0;
 
         for member in &current_state.roles {
-            let message_to_send: String;
+            // Anyone added without a role yet can't be sent one
+            let character = match &member.2 {
+                Some(value) => value,
+                None => {
+                    send_msg(
+                        msg,
+                        ctx,
+                        format!(
+                            "**Error:** {} doesn't have a role yet!",
+                            get_member_name(&member.1)
+                        ),
+                    )
+                    .await;
+                    continue;
+                }
+            };
 
-            if let Some(value) = &member.2.as_ref().unwrap().decoy_character {
-                message_to_send = format!(
-                    "Your role this game is the **{}**, a **{}**.",
-                    &value.name, &value.char_type_str,
-                );
-            } else {
-                message_to_send = format!(
-                    "Your role this game is the **{}**, a **{}**.",
-                    &member.2.as_ref().unwrap().name,
-                    &member.2.as_ref().unwrap().char_type_str,
-                );
-            }
+            let message_to_send = get_role_message(&current_state, character);
 
             let result = &member
                 .1
@@ -913,13 +2011,22 @@ async fn dm_roles(ctx: &Context, msg: &Message) {
     // Once completed without errors, gamestate is set to playing
     current_state.game_state = GameState::Playing;
 
+    // The Spirit of Ivory counts extra evil players from here
+    current_state.starting_evil = current_state.count_evil();
+
+    update_nicknames(ctx, &mut current_state).await;
+
     set_database(current_state).await;
+
+    update_control_panel(ctx, *guild_id).await;
+
+    update_status_board(ctx, *guild_id).await;
 }
 
-async fn night(ctx: &Context, msg: &Message) {
+async fn night(ctx: &Context, msg: &impl CommandSource) {
     print_command(&msg);
 
-    let guild_id = msg.guild_id.as_ref().unwrap().as_u64();
+    let guild_id = &msg.guild_id().unwrap().0;
 
     let mut current_state = get_database(&guild_id).await;
 
@@ -929,85 +2036,41 @@ async fn night(ctx: &Context, msg: &Message) {
     }
 
     current_state.time = Time::Night;
-    let title: &str;
-    let mut content = String::from("");
 
-    let mut characters = current_state.roles.clone();
+    // Tonight's deaths start fresh, and anything held back from last dawn is
+    // dropped, so those players are shown as dead from now on
+    let held_deaths = !current_state.night_deaths.is_empty();
 
-    if current_state.day_index == 1 {
-        title = "First Night Order";
-        characters.sort_by_key(|d| d.2.as_ref().unwrap().first_order_index.clone());
+    current_state.night_deaths.clear();
+    current_state.dawn_pending = false;
 
-        let mut index: u32 = 1;
+    if held_deaths {
+        update_nicknames(ctx, &mut current_state).await;
+    }
 
-        for character in characters.clone() {
-            let character_role = character.2.as_ref().unwrap();
+    // Last night's choices are finished with, and their menus stop working
+    current_state.night_choices.clear();
 
-            if character_role.first_order_index != -1 {
-                // Try to find a user.nick_in(), but if it doesn't exist, use user.name
-                let name = match character.1.user.nick_in(&ctx, *guild_id).await {
-                    Some(value) => value,
-                    None => character.1.user.name.clone(),
-                };
+    stop_timer(&mut current_state);
 
-                content.push_str(
-                    format!(
-                        "{}) **{}** as the {}\n",
-                        index, name, character_role.name
-                    )
-                    .as_str(),
-                );
+    let title: &str;
+    let mut content = String::from("");
 
-                index += 1;
-            }
-        }
+    let mut characters = current_state.roles.clone();
+
+    // Players who wake get the first rooms, so they're sorted by the night order.
+    // Anyone still waiting for a role doesn't wake, but needs a room all the same
+    if current_state.day_index == 1 {
+        title = "First Night Order";
+        characters.sort_by_key(|d| d.2.as_ref().map_or(-1, |c| c.first_order_index));
     } else {
         title = "Night Order";
-        characters.sort_by_key(|d| d.2.as_ref().unwrap().order_index.clone());
-
-        let mut index: u32 = 1;
-
-        for character in characters.clone() {
-            let character_role = character.2.as_ref().unwrap();
-            if character_role.order_index != -1 {
-                let warning: &str;
-
-                match character_role.night_action {
-                    ActionTime::OnlyFirstNight => warning = " *if triggered*",
-                    ActionTime::VariableNight => warning = " *if triggered*",
-                    ActionTime::DeathNight => warning = " *if triggered*",
-                    _ => warning = "",
-                }
-                
-                let name = match character.1.user.nick_in(&ctx, *guild_id).await {
-                    Some(value) => value,
-                    None => character.1.user.name.clone(),
-                };
-
-                content.push_str(
-                    format!(
-                        "{}) **{}** as the {}{}\n",
-                        index, name, character_role.name, warning,
-                    )
-                    .as_str(),
-                );
-
-                index += 1;
-            }
-        }
+        characters.sort_by_key(|d| d.2.as_ref().map_or(-1, |c| c.order_index));
     }
 
-    let _ = msg
-        .channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|mut e| {
-                e.title(title);
-                e.description(content);
-                e
-            });
-            m
-        })
-        .await;
+    content.push_str(&current_state.get_night_order_text());
+
+    send_embed(msg, ctx, String::from(title), content, None).await;
 
     send_msg(&msg, &ctx, String::from("Sending members to sleep...")).await;
 
@@ -1047,13 +2110,18 @@ async fn night(ctx: &Context, msg: &Message) {
         let mut taken_channels: Vec<bool> = vec![false; night_channels.len()];
 
         if &night_channels.len() >= &current_state.roles.len() {
+            let mut moves: Vec<MoveRequest> = Vec::new();
+
             for member in characters {
-                let character_role = member.2.as_ref().unwrap();
                 let mut found_channel: Option<GuildChannel> = None;
 
-                if (current_state.day_index == 1 && character_role.first_order_index == -1)
-                    || (current_state.day_index != 1 && character_role.order_index == -1)
-                {
+                let wakes = match &member.2 {
+                    Some(value) if current_state.day_index == 1 => value.first_order_index != -1,
+                    Some(value) => value.order_index != -1,
+                    None => false,
+                };
+
+                if !wakes {
                     let mut index: usize = night_channels.len() - 1;
                     for value in r_night_channels.clone() {
                         if taken_channels.get(index).unwrap() == &false {
@@ -1079,8 +2147,16 @@ async fn night(ctx: &Context, msg: &Message) {
                 }
 
                 if let Some(value) = found_channel {
-                    // Move them to the assigned room
-                    member.1.move_to_voice_channel(&ctx.http, value).await;
+                    // Remember the room so the Storyteller can be moved there later
+                    current_state
+                        .night_rooms
+                        .insert(member.0, *value.id.as_u64());
+
+                    moves.push(MoveRequest {
+                        user_id: member.1.user.id,
+                        name: get_member_name(&member.1),
+                        channel_id: value.id,
+                    });
                 } else {
                     send_msg(
                         &msg,
@@ -1094,9 +2170,25 @@ async fn night(ctx: &Context, msg: &Message) {
                 }
             }
 
+            // Restart the walkthrough for the new night
+            current_state.night_step = 0;
+
+            lock_text_channels(ctx, &mut current_state).await;
+
+            // Save before moving anyone, so the watchdog knows where everyone belongs
             set_database(current_state).await;
 
-            send_msg(&msg, &ctx, String::from("**Sent!**")).await;
+            // Move them to their assigned rooms
+            let failures = move_members(&ctx.http, GuildId(*guild_id), moves).await;
+
+            report_failed_moves(ctx, msg, failures).await;
+
+            send_msg(
+                msg,
+                ctx,
+                String::from("**Sent!** Type \"next\" to walk through the night order!"),
+            )
+            .await;
         } else {
             send_msg(
                 &msg,
@@ -1113,20 +2205,47 @@ async fn night(ctx: &Context, msg: &Message) {
         )
         .await;
     }
+
+    // Whisper channels are only deleted once everyone has been moved out of
+    // them, so nobody is dropped from voice on the way to their room
+    let whispers = end_game_whispers(ctx, *guild_id).await;
+
+    if whispers > 0 {
+        send_msg(msg, ctx, format!("Closed {} whisper channels", whispers)).await;
+    }
+
+    update_control_panel(ctx, *guild_id).await;
+
+    update_status_board(ctx, *guild_id).await;
+
+    send_dusk_choices(ctx, msg, *guild_id).await;
 }
 
-async fn day(ctx: &Context, msg: &Message) {
+async fn day(ctx: &Context, msg: &impl CommandSource) {
     print_command(&msg);
 
     send_msg(&msg, &ctx, String::from("Waking up members...")).await;
 
-    let guild_id = msg.guild_id.as_ref().unwrap().as_u64();
+    let guild_id = &msg.guild_id().unwrap().0;
 
     let mut current_state = get_database(&guild_id).await;
 
+    let was_night = matches!(current_state.time, Time::Night);
+
+    if was_night {
+        current_state.dawn_pending = true;
+    }
+
     current_state.time = Time::Day;
 
-    set_database(current_state.clone()).await;
+    lift_night_lockdown(ctx, &mut current_state).await;
+
+    let expired = expire_night_tokens(&mut current_state);
+
+    if expired > 0 {
+        send_msg(msg, ctx, format!("Removed {} reminder tokens that only last the night", expired))
+            .await;
+    }
 
     if &current_state.roles.len() > &(0 as usize) {
         let all_channels = GuildId(guild_id.clone()).channels(&ctx.http).await.unwrap();
@@ -1144,13 +2263,26 @@ async fn day(ctx: &Context, msg: &Message) {
         }
 
         if let Some(value) = town_voice_channel {
-            for member in &current_state.roles {
-                member
-                    .1
-                    .move_to_voice_channel(&ctx.http, value.clone())
-                    .await;
-            }
+            current_state.town_channel = Some(*value.id.as_u64());
+
+            set_database(current_state.clone()).await;
+
+            let moves: Vec<MoveRequest> = current_state
+                .roles
+                .iter()
+                .map(|member| MoveRequest {
+                    user_id: member.1.user.id,
+                    name: get_member_name(&member.1),
+                    channel_id: value.id,
+                })
+                .collect();
+
+            let failures = move_members(&ctx.http, GuildId(*guild_id), moves).await;
+
+            report_failed_moves(ctx, msg, failures).await;
         } else {
+            set_database(current_state.clone()).await;
+
             send_msg(
                 &msg,
                 &ctx,
@@ -1159,6 +2291,8 @@ async fn day(ctx: &Context, msg: &Message) {
             .await;
         }
     } else {
+        set_database(current_state.clone()).await;
+
         send_msg(
             &msg,
             &ctx,
@@ -1166,12 +2300,20 @@ async fn day(ctx: &Context, msg: &Message) {
         )
         .await;
     }
+
+    update_control_panel(ctx, *guild_id).await;
+
+    update_status_board(ctx, *guild_id).await;
+
+    if was_night {
+        ask_to_announce_deaths(ctx, &current_state).await;
+    }
 }
 
-async fn edit_role(ctx: &Context, msg: &Message) {
+async fn edit_role(ctx: &Context, msg: &impl CommandSource) {
     print_command(&msg);
 
-    let guild_id = msg.guild_id.as_ref().unwrap().as_u64();
+    let guild_id = &msg.guild_id().unwrap().0;
 
     // Start accesssing main database with lock
     let lock = BLOOD_DATABASE.lock().await;
@@ -1181,7 +2323,7 @@ async fn edit_role(ctx: &Context, msg: &Message) {
     drop(lock);
     // Unlock main database
 
-    let params: Vec<&str> = msg.content.split(" ").collect();
+    let params: Vec<&str> = msg.content().split(" ").collect();
 
     if params.len() == 2 {
         let try_num = params.get(1).unwrap().parse::<u16>();
@@ -1197,9 +2339,13 @@ async fn edit_role(ctx: &Context, msg: &Message) {
                 let role_to_edit = current_state.roles.get((num - 1) as usize).unwrap();
 
                 let role_to_return = (role_to_edit.0.clone(), role_to_edit.1.clone(), None);
+                let role_to_edit_id = role_to_edit.0;
 
                 current_state.roles[(num - 1) as usize] = role_to_return;
 
+                // Their old menu is finished, so they'll get a fresh one
+                current_state.role_menus.remove(&role_to_edit_id);
+
                 current_state.game_state = GameState::SettingRoles;
 
                 ask_for_role(&ctx, &msg, current_state).await;
@@ -1217,56 +2363,728 @@ async fn edit_role(ctx: &Context, msg: &Message) {
     }
 }
 
-async fn nothing(ctx: &Context, msg: &Message) {
-    let content = String::from("Command not found. Please try again!");
-    send_msg(&msg, &ctx, content).await;
-}
+async fn set_life(ctx: &Context, msg: &impl CommandSource, alive: bool) {
+    print_command(msg);
 
-// Helper functions
+    let guild_id = &msg.guild_id().unwrap().0;
 
-async fn ask_for_role(ctx: &Context, msg: &Message, mut current_state: BloodGuild) {
-    let mut sent_request = false;
+    let mut current_state = get_database(guild_id).await;
 
-    for user_tuple in current_state.roles.clone() {
-        if user_tuple.2.is_some() == false {
-            sent_request = true;
+    let params: Vec<&str> = msg.content().split(' ').collect();
 
-            let mut user_name: String = String::from(&user_tuple.1.user.name);
+    let seat = match params.get(1).and_then(|p| current_state.find_seat(p)) {
+        Some(value) => value,
+        None => {
+            send_msg(msg, ctx, String::from("Please provide a seat number or mention!")).await;
+            return;
+        }
+    };
 
-            if let Some(value) = &user_tuple.1.nick {
-                user_name = value.clone();
-            }
+    let user_id = current_state.roles[seat].0;
+    let name = get_member_name(&current_state.roles[seat].1);
 
-            send_msg(
-                &msg,
-                &ctx,
-                String::from(format!("**Enter role** for *{}*", user_name)),
-            )
+    let order = current_state.get_night_order();
+
+    if alive {
+        current_state.dead.remove(&user_id);
+        current_state.used_ghost_votes.remove(&user_id);
+        current_state.night_deaths.retain(|id| *id != user_id);
+
+        print_info(&format!("User {} was revived", name));
+        send_msg(msg, ctx, format!("*{}* is **alive** again", name)).await;
+    } else {
+        current_state.dead.insert(user_id);
+
+        // Saved for the dawn announcement
+        if let Time::Night = current_state.time {
+            if !current_state.night_deaths.contains(&user_id) {
+                current_state.night_deaths.push(user_id);
+            }
+        }
+
+        print_info(&format!("User {} died", name));
+        send_msg(msg, ctx, format!("*{}* is now **dead**", name)).await;
+    }
+
+    if let Time::Night = current_state.time {
+        current_state.keep_night_step(&order);
+    }
+
+    update_nicknames(ctx, &mut current_state).await;
+
+    set_database(current_state).await;
+
+    update_control_panel(ctx, *guild_id).await;
+
+    update_status_board(ctx, *guild_id).await;
+}
+
+async fn use_ghost_vote(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seat = match params.get(1).and_then(|p| current_state.find_seat(p)) {
+        Some(value) => value,
+        None => {
+            send_msg(msg, ctx, String::from("Please provide a seat number or mention!")).await;
+            return;
+        }
+    };
+
+    let user_id = current_state.roles[seat].0;
+    let name = get_member_name(&current_state.roles[seat].1);
+
+    if current_state.is_alive(user_id) {
+        send_msg(msg, ctx, format!("*{}* is alive, so they don't have a ghost vote!", name))
             .await;
+        return;
+    }
 
-            break;
-        } else if &user_tuple.2.clone().unwrap().char_type_str == "Decoy"
-            && &user_tuple.2.clone().unwrap().decoy_character.is_some() == &false
-        {
-            sent_request = true;
+    // Using it again gives it back, in case of a mistake
+    if current_state.used_ghost_votes.remove(&user_id) {
+        send_msg(msg, ctx, format!("*{}* has their ghost vote back", name)).await;
+    } else {
+        current_state.used_ghost_votes.insert(user_id);
+        send_msg(msg, ctx, format!("*{}* has used their ghost vote", name)).await;
+    }
 
-            let mut user_name: String = String::from(&user_tuple.1.user.name);
+    update_nicknames(ctx, &mut current_state).await;
 
-            if let Some(value) = &user_tuple.1.nick {
-                user_name = value.clone();
+    set_database(current_state).await;
+
+    update_status_board(ctx, *guild_id).await;
+}
+
+async fn set_nicknames(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    match params.get(1).copied() {
+        Some("on") => {
+            current_state.nicknames = true;
+
+            // Don't rename anyone until roles are out
+            if let GameState::Playing = current_state.game_state {
+                update_nicknames(ctx, &mut current_state).await;
             }
+        }
+        Some("off") => {
+            current_state.nicknames = false;
 
+            restore_nicknames(ctx, &mut current_state).await;
+        }
+        _ => {
+            send_msg(msg, ctx, String::from("Usage: \"nicknames on/off\"")).await;
+            return;
+        }
+    }
+
+    let content = format!(
+        "Player nicknames **{}** be managed",
+        if current_state.nicknames { "will" } else { "won't" }
+    );
+
+    set_database(current_state).await;
+
+    send_msg(msg, ctx, content).await;
+}
+
+async fn night_walkthrough(ctx: &Context, msg: &impl CommandSource, walk: Walk) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    match current_state.time {
+        Time::Night => (),
+        Time::Day => {
             send_msg(
-                &msg,
-                &ctx,
-                String::from(format!("**Enter decoy role** for *{}*", user_name)),
+                msg,
+                ctx,
+                String::from("**Error:** It's not night! Type \"night\" first."),
             )
             .await;
+            return;
+        }
+    }
 
-            break;
+    let order = current_state.get_night_order();
+
+    if order.is_empty() {
+        send_msg(msg, ctx, String::from("Nobody wakes tonight!")).await;
+        return;
+    }
+
+    // Steps are counted from 1, with 0 being dusk and order.len() + 1 being dawn
+    let new_step = match walk {
+        Walk::Next => current_state.night_step + 1,
+        Walk::Back => current_state.night_step.saturating_sub(1),
+        Walk::Skip => current_state.night_step + 2,
+    };
+    let new_step = new_step.min(order.len() + 1);
+
+    if walk == Walk::Skip && current_state.night_step < order.len() {
+        let skipped = &order[current_state.night_step];
+
+        send_msg(
+            msg,
+            ctx,
+            format!("Skipped {}", current_state.get_night_step_line(skipped)),
+        )
+        .await;
+    }
+
+    current_state.night_step = new_step;
+    set_database(current_state.clone()).await;
+
+    if new_step == 0 {
+        send_msg(
+            msg,
+            ctx,
+            String::from("Back at dusk. Type \"next\" to wake the first player!"),
+        )
+        .await;
+        return;
+    } else if new_step > order.len() {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**The night is over!** Type \"day\" to wake everyone up."),
+        )
+        .await;
+        return;
+    }
+
+    let step = order[new_step - 1].clone();
+    let character = &step.character;
+
+    // Fabled aren't anyone's, so there's no room to go to
+    let user_id = step.player.as_ref().map_or(0, |p| p.0);
+
+    // Follow the waking player into their night room
+    if let Some(room) = current_state.night_rooms.get(&user_id) {
+        if let Err(why) = GuildId(*guild_id)
+            .move_member(&ctx.http, msg.author().id, ChannelId(*room))
+            .await
+        {
+            print_error(&format!("Could not move storyteller: {:?}", why));
+
+            send_msg(
+                msg,
+                ctx,
+                String::from("**Error:** Could not move you, are you in voice chat?"),
+            )
+            .await;
+        }
+    }
+
+    send_step_choice(ctx, &mut current_state, user_id).await;
+
+    let mut content = format!("{}\n\n", current_state.get_night_step_line(&step));
+
+    content.push_str(&format!("*{}*\n", character.ability));
+
+    if let Some(decoy) = &character.decoy_character {
+        content.push_str(&format!(
+            "\nBelieves they are the **{}**:\n*{}*\n",
+            decoy.name, decoy.ability
+        ));
+    }
+
+    if !character.reminders.is_empty() {
+        content.push_str(&format!("\n**Reminders:** {}\n", character.reminders.join(", ")));
+    }
+
+    for (other, rule) in current_state.get_active_jinxes(&character.name) {
+        content.push_str(&format!("\n**Jinxed with the {}:** {}\n", other, rule));
+    }
+
+    if let Some(info) = current_state.get_suggested_info(user_id) {
+        content.push_str(&format!("\n**Suggested info:** {}\n", info));
+    }
+
+    if let Some(choice) = get_choice_summary(&current_state, user_id) {
+        content.push_str(&format!("\n**Chose:** {}\n", choice));
+    }
+
+    let title = format!("Step {} of {}", new_step, order.len());
+    let footer = current_state.get_time_str();
+
+    send_embed(msg, ctx, title, content, Some(footer)).await;
+}
+
+async fn set_watchdog(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    match (params.get(1).copied(), params.get(2).copied()) {
+        (Some("night"), Some("off")) => current_state.night_watch = WatchPolicy::Off,
+        (Some("night"), Some("warn")) => current_state.night_watch = WatchPolicy::Warn,
+        (Some("night"), Some("return")) => current_state.night_watch = WatchPolicy::Return,
+        (Some("day"), Some("off")) => current_state.day_watch = false,
+        (Some("day"), Some("on")) => current_state.day_watch = true,
+        (None, None) => (),
+        _ => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Usage: \"watch night off/warn/return\" or \"watch day on/off\""),
+            )
+            .await;
+            return;
+        }
+    }
+
+    let content = format!(
+        "**Watchdog**\nNight rooms: {}\nTown square: {}",
+        current_state.night_watch.as_str(),
+        if current_state.day_watch { "Warn the Storyteller" } else { "Off" }
+    );
+
+    set_database(current_state).await;
+
+    send_msg(msg, ctx, content).await;
+}
+
+async fn set_locked_channels(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let channel = params.get(2).and_then(serenity::utils::parse_channel);
+
+    match (params.get(1).copied(), channel) {
+        (Some("add"), Some(id)) => {
+            if !current_state.locked_channels.contains(&id) {
+                current_state.locked_channels.push(id);
+            }
+        }
+        (Some("remove"), Some(id)) => current_state.locked_channels.retain(|c| *c != id),
+        (None, _) => (),
+        _ => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Usage: \"lock add #channel\" or \"lock remove #channel\""),
+            )
+            .await;
+            return;
+        }
+    }
+
+    let mut content = String::from("**Text channels locked at night:**\n");
+
+    if current_state.locked_channels.is_empty() {
+        content.push_str("None");
+    }
+
+    for id in &current_state.locked_channels {
+        content.push_str(&format!("<#{}>\n", id));
+    }
+
+    set_database(current_state).await;
+
+    send_msg(msg, ctx, content).await;
+}
+
+async fn set_public_channel(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    match params.get(1).map(serenity::utils::parse_channel) {
+        Some(Some(id)) => {
+            // A new channel gets its own status board
+            if current_state.public_channel != Some(id) {
+                current_state.status_board = None;
+            }
+
+            current_state.public_channel = Some(id);
+        }
+        None => (),
+        Some(None) => {
+            send_msg(msg, ctx, String::from("Usage: \"public #channel\"")).await;
+            return;
+        }
+    }
+
+    let content = match current_state.public_channel {
+        Some(id) => format!("**Public channel:** <#{}>", id),
+        None => String::from("**Public channel:** None"),
+    };
+
+    set_database(current_state).await;
+
+    send_msg(msg, ctx, content).await;
+
+    update_status_board(ctx, *guild_id).await;
+}
+
+async fn set_night_mute(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    match params.get(1).copied() {
+        Some("on") => current_state.night_mute = true,
+        Some("off") => current_state.night_mute = false,
+        None => (),
+        _ => {
+            send_msg(msg, ctx, String::from("Usage: \"nightmute on/off\"")).await;
+            return;
+        }
+    }
+
+    let content = format!(
+        "Players outside their own night room **{}** be server muted",
+        if current_state.night_mute { "will" } else { "won't" }
+    );
+
+    set_database(current_state).await;
+
+    send_msg(msg, ctx, content).await;
+}
+
+async fn lock_text_channels(ctx: &Context, current_state: &mut BloodGuild) {
+    // Already locked from an earlier "night", so the saved overwrites are still correct
+    if !current_state.saved_overwrites.is_empty() {
+        return;
+    }
+
+    for id in current_state.locked_channels.clone() {
+        let channel = match ChannelId(id).to_channel(ctx).await {
+            Ok(Channel::Guild(value)) => value,
+            _ => {
+                print_error(&format!("Could not find text channel {} to lock", id));
+                continue;
+            }
+        };
+
+        // Keep a copy of every overwrite so day can put them back exactly
+        current_state
+            .saved_overwrites
+            .insert(id, channel.permission_overwrites.clone());
+
+        for player in &current_state.roles {
+            lock_channel_for(ctx, &channel, player.0).await;
+        }
+    }
+}
+
+// Takes away sending messages, keeping whatever else their overwrite allowed
+async fn lock_channel_for(ctx: &Context, channel: &GuildChannel, user_id: u64) {
+    let kind = PermissionOverwriteType::Member(UserId(user_id));
+
+    let previous = channel
+        .permission_overwrites
+        .iter()
+        .find(|o| o.kind == kind);
+
+    let overwrite = PermissionOverwrite {
+        allow: previous.map_or(Permissions::empty(), |o| o.allow) - Permissions::SEND_MESSAGES,
+        deny: previous.map_or(Permissions::empty(), |o| o.deny) | Permissions::SEND_MESSAGES,
+        kind,
+    };
+
+    if let Err(why) = channel.create_permission(&ctx.http, &overwrite).await {
+        print_error(&format!(
+            "Could not lock {} for {}: {:?}",
+            channel.id, user_id, why
+        ));
+    }
+}
+
+/// Locks the text channels for someone who joins while they're locked for the night
+async fn lock_new_player(ctx: &Context, current_state: &BloodGuild, user_id: u64) {
+    for id in current_state.saved_overwrites.keys() {
+        match ChannelId(*id).to_channel(ctx).await {
+            Ok(Channel::Guild(channel)) => lock_channel_for(ctx, &channel, user_id).await,
+            _ => print_error(&format!("Could not find text channel {} to lock", id)),
+        }
+    }
+}
+
+async fn lift_night_lockdown(ctx: &Context, current_state: &mut BloodGuild) {
+    for (id, overwrites) in current_state.saved_overwrites.drain() {
+        for player in &current_state.roles {
+            let kind = PermissionOverwriteType::Member(UserId(player.0));
+
+            // Put back the overwrite they had before night, or remove ours if there wasn't one
+            let result = match overwrites.iter().find(|o| o.kind == kind) {
+                Some(previous) => ChannelId(id).create_permission(&ctx.http, previous).await,
+                None => ChannelId(id).delete_permission(&ctx.http, kind).await,
+            };
+
+            if let Err(why) = result {
+                print_error(&format!("Could not unlock {} for {}: {:?}", id, player.0, why));
+            }
+        }
+    }
+
+    // Anyone not in voice can't be unmuted, so they're kept until they join again
+    let mut still_muted = HashSet::new();
+
+    for user_id in current_state.muted.drain() {
+        if !unmute_player(ctx, current_state.id, user_id).await {
+            still_muted.insert(user_id);
+        }
+    }
+
+    current_state.muted = still_muted;
+}
+
+/// Unmutes someone we muted, returning whether it worked
+async fn unmute_player(ctx: &Context, guild_id: u64, user_id: u64) -> bool {
+    match GuildId(guild_id)
+        .edit_member(&ctx.http, user_id, |m| m.mute(false))
+        .await
+    {
+        Ok(_) => true,
+        Err(why) => {
+            print_error(&format!("Could not unmute {}: {:?}", user_id, why));
+            false
+        }
+    }
+}
+
+async fn watch_voice_state(
+    ctx: &Context,
+    guild_id: GuildId,
+    old: Option<VoiceState>,
+    new: VoiceState,
+) {
+    let old_channel = old.and_then(|o| o.channel_id);
+
+    // Mutes and deafens also come through here, so only look at actual moves
+    if old_channel == new.channel_id {
+        return;
+    }
+
+    let user_id = *new.user_id.as_u64();
+
+    // Start accesssing main database with lock
+    let lock = BLOOD_DATABASE.lock().await;
+
+    let left_muted = lock
+        .left_muted
+        .get(guild_id.as_u64())
+        .is_some_and(|m| m.contains(&user_id));
+
+    let current_state = lock.blood_guilds.get(guild_id.as_u64()).cloned();
+
+    drop(lock);
+    // Unlock main database
+
+    // Muted by a game that has ended, and back in voice where they can be unmuted
+    if left_muted
+        && new.channel_id.is_some()
+        && unmute_player(ctx, *guild_id.as_u64(), user_id).await
+    {
+        if let Some(value) = BLOOD_DATABASE
+            .lock()
+            .await
+            .left_muted
+            .get_mut(guild_id.as_u64())
+        {
+            value.remove(&user_id);
         }
     }
 
+    let current_state = match current_state {
+        Some(value) => value,
+        None => return,
+    };
+
+    // Anyone who couldn't be unmuted at dawn is unmuted once they're back in voice
+    let night_muting = matches!(current_state.time, Time::Night) && current_state.night_mute;
+
+    if !night_muting
+        && new.channel_id.is_some()
+        && current_state.muted.contains(&user_id)
+        && unmute_player(ctx, *guild_id.as_u64(), user_id).await
+    {
+        if let Some(value) = BLOOD_DATABASE
+            .lock()
+            .await
+            .blood_guilds
+            .get_mut(guild_id.as_u64())
+        {
+            value.muted.remove(&user_id);
+        }
+    }
+
+    let player = match current_state.roles.iter().find(|p| p.0 == user_id) {
+        Some(value) => value,
+        None => return,
+    };
+
+    let name = get_member_name(&player.1);
+    let storyteller_channel = ChannelId(current_state.storyteller_channel);
+
+    let now_in = match new.channel_id {
+        Some(value) => format!("now in <#{}>", value.as_u64()),
+        None => String::from("disconnected"),
+    };
+
+    let warning: Option<String> = match current_state.time {
+        Time::Night => {
+            let room = match current_state.night_rooms.get(&user_id) {
+                Some(value) => ChannelId(*value),
+                None => return,
+            };
+
+            if current_state.night_mute {
+                let in_room = new.channel_id == Some(room);
+                let is_muted = current_state.muted.contains(&user_id);
+
+                // Only players in voice can be muted, and only those we muted get unmuted
+                if (new.channel_id.is_some() && !in_room && !is_muted) || (in_room && is_muted) {
+                    match guild_id
+                        .edit_member(&ctx.http, new.user_id, |m| m.mute(!in_room))
+                        .await
+                    {
+                        Ok(_) => {
+                            // Changed on the live state, as it may have moved on while we waited
+                            let mut lock = BLOOD_DATABASE.lock().await;
+
+                            if let Some(value) = lock.blood_guilds.get_mut(guild_id.as_u64()) {
+                                if in_room {
+                                    value.muted.remove(&user_id);
+                                } else {
+                                    value.muted.insert(user_id);
+                                }
+                            }
+                        }
+                        Err(why) => print_error(&format!("Could not mute {}: {:?}", name, why)),
+                    }
+                }
+            }
+
+            if new.channel_id == Some(room) {
+                return;
+            }
+
+            match current_state.night_watch {
+                WatchPolicy::Off => None,
+                WatchPolicy::Warn => Some(format!(
+                    "**Watchdog:** *{}* left their night room ({})",
+                    name, now_in
+                )),
+                WatchPolicy::Return => {
+                    // Disconnected players can't be moved, so just warn about them
+                    if new.channel_id.is_some() {
+                        match guild_id.move_member(&ctx.http, new.user_id, room).await {
+                            Ok(_) => Some(format!(
+                                "**Watchdog:** *{}* wandered out of their night room, so they were sent back",
+                                name
+                            )),
+                            Err(why) => {
+                                print_error(&format!("Could not return {}: {:?}", name, why));
+
+                                Some(format!(
+                                    "**Watchdog:** *{}* left their night room ({}) and could not be sent back!",
+                                    name, now_in
+                                ))
+                            }
+                        }
+                    } else {
+                        Some(format!(
+                            "**Watchdog:** *{}* left their night room ({})",
+                            name, now_in
+                        ))
+                    }
+                }
+            }
+        }
+        Time::Day => {
+            // Going off to whisper is allowed, and already announced
+            let whispering = current_state
+                .whispers
+                .iter()
+                .any(|w| new.channel_id == Some(ChannelId(w.channel)));
+
+            match current_state.town_channel {
+                Some(town)
+                    if current_state.day_watch
+                        && !whispering
+                        && old_channel == Some(ChannelId(town)) =>
+                {
+                    Some(format!(
+                        "**Watchdog:** *{}* left the town square ({})",
+                        name, now_in
+                    ))
+                }
+                _ => None,
+            }
+        }
+    };
+
+    if let Some(content) = warning {
+        print_info(&format!("{} moved, {}", name, now_in));
+
+        if let Err(why) = storyteller_channel.say(&ctx.http, content).await {
+            print_error(&format!("Could not send message: {:?}", why));
+        }
+    }
+}
+
+async fn nothing(ctx: &Context, msg: &impl CommandSource) {
+    let content = String::from("Command not found. Please try again!");
+    send_msg(&msg, &ctx, content).await;
+}
+
+// Helper functions
+
+// Travelers who join evil are extra evil players too, as far as the Spirit of Ivory cares
+fn count_evil_characters<'a>(characters: impl Iterator<Item = &'a Character>) -> usize {
+    characters
+        .filter(|c| matches!(c.alignment, Alignment::Evil))
+        .count()
+}
+
+fn get_member_name(member: &Member) -> String {
+    match &member.nick {
+        Some(value) => value.clone(),
+        None => member.user.name.clone(),
+    }
+}
+
+async fn ask_for_role(ctx: &Context, msg: &impl CommandSource, mut current_state: BloodGuild) {
+    // Anyone still missing a role (or a believed role) gets a menu to pick it
+    let sent_request = current_state.roles.iter().any(|p| needs_role(&p.2));
+
+    if sent_request {
+        post_role_menus(ctx, &mut current_state).await;
+    }
+
     if sent_request == false {
         let mut content: String = String::from("");
         let mut num: u16 = 1;
@@ -1288,17 +3106,7 @@ async fn ask_for_role(ctx: &Context, msg: &Message, mut current_state: BloodGuil
             num += 1;
         }
 
-        let _ = msg
-            .channel_id
-            .send_message(&ctx.http, |m| {
-                m.embed(|mut e| {
-                    e.title("Role List:");
-                    e.description(content);
-                    e
-                });
-                m
-            })
-            .await;
+        send_embed(msg, ctx, String::from("Role List:"), content, None).await;
 
         send_msg(
             &msg,
@@ -1310,8 +3118,11 @@ async fn ask_for_role(ctx: &Context, msg: &Message, mut current_state: BloodGuil
         .await;
 
         current_state.game_state = GameState::SettingUp;
+        current_state.role_menus.clear();
     }
 
+    let guild_id = current_state.id;
+
     // Start accesssing main database with lock
     let mut lock = BLOOD_DATABASE.lock().await;
 
@@ -1319,6 +3130,113 @@ async fn ask_for_role(ctx: &Context, msg: &Message, mut current_state: BloodGuil
 
     drop(lock);
     // Unlock main database
+
+    update_control_panel(ctx, guild_id).await;
+
+    update_status_board(ctx, guild_id).await;
+}
+
+async fn update_nicknames(ctx: &Context, current_state: &mut BloodGuild) {
+    if !current_state.nicknames {
+        return;
+    }
+
+    let mut saved_new = false;
+
+    for player in &current_state.roles {
+        if let hash_map::Entry::Vacant(entry) = current_state.original_nicks.entry(player.0) {
+            entry.insert(player.1.nick.clone());
+
+            saved_new = true;
+        }
+    }
+
+    // Write them to disk first, so they can be restored even if the bot stops mid-game
+    if saved_new {
+        save_original_nicks(current_state.id, &current_state.original_nicks);
+    }
+
+    for seat in 0..current_state.roles.len() {
+        let nick = current_state.get_decorated_nick(seat);
+
+        if let Err(why) = GuildId(current_state.id)
+            .edit_member(&ctx.http, current_state.roles[seat].0, |m| m.nickname(&nick))
+            .await
+        {
+            print_error(&format!("Could not set nickname {}: {:?}", nick, why));
+        }
+    }
+}
+
+async fn restore_nicknames(ctx: &Context, current_state: &mut BloodGuild) {
+    if current_state.original_nicks.is_empty() {
+        return;
+    }
+
+    for (user_id, nick) in current_state.original_nicks.drain() {
+        // An empty nickname removes it, going back to their username
+        let nick = nick.unwrap_or_default();
+
+        if let Err(why) = GuildId(current_state.id)
+            .edit_member(&ctx.http, user_id, |m| m.nickname(&nick))
+            .await
+        {
+            print_error(&format!("Could not restore nickname of {}: {:?}", user_id, why));
+        }
+    }
+
+    save_original_nicks(current_state.id, &current_state.original_nicks);
+}
+
+async fn restore_saved_nicknames(ctx: &Context) {
+    // Ready also fires on reconnects, so leave games that are still running alone
+    let active: Vec<u64> = BLOOD_DATABASE.lock().await.blood_guilds.keys().copied().collect();
+
+    let saved: Vec<(u64, u64, Option<String>)> = load_original_nicks()
+        .into_iter()
+        .filter(|n| !active.contains(&n.0))
+        .collect();
+
+    if saved.is_empty() {
+        return;
+    }
+
+    print_status(&format!("Restoring {} nicknames from last time...", saved.len()));
+
+    let mut guilds: HashSet<u64> = HashSet::new();
+
+    for (guild_id, user_id, nick) in saved {
+        guilds.insert(guild_id);
+
+        let nick = nick.unwrap_or_default();
+
+        if let Err(why) = GuildId(guild_id)
+            .edit_member(&ctx.http, user_id, |m| m.nickname(&nick))
+            .await
+        {
+            print_error(&format!("Could not restore nickname of {}: {:?}", user_id, why));
+        }
+    }
+
+    for guild_id in guilds {
+        save_original_nicks(guild_id, &HashMap::new());
+    }
+}
+
+async fn report_failed_moves(ctx: &Context, msg: &impl CommandSource, failures: Vec<FailedMove>) {
+    if failures.is_empty() {
+        return;
+    }
+
+    let mut content = format!("**Could not move {} player(s):**\n", failures.len());
+
+    for failure in failures {
+        print_error(&format!("Could not move {}: {}", failure.name, failure.reason));
+
+        content.push_str(&format!("*{}*: {}\n", failure.name, failure.reason));
+    }
+
+    send_msg(msg, ctx, content).await;
 }
 
 async fn get_database(guild_id: &u64) -> BloodGuild {
@@ -1342,3 +3260,34 @@ async fn set_database(current_state: BloodGuild) {
     drop(lock);
     // Unlock main database
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_character(char_type: CharacterType, alignment: Alignment) -> Character {
+        let mut character = Character::new(
+            String::from("Test"),
+            char_type,
+            -1,
+            -1,
+            ActionTime::NoNight,
+            String::new(),
+            Vec::new(),
+        );
+        character.alignment = alignment;
+        character
+    }
+
+    #[test]
+    fn counts_evil_travelers() {
+        let characters = [
+            test_character(CharacterType::Demon, Alignment::Evil),
+            test_character(CharacterType::Townsfolk, Alignment::Good),
+            test_character(CharacterType::Traveler, Alignment::Evil),
+            test_character(CharacterType::Traveler, Alignment::Good),
+        ];
+
+        assert_eq!(count_evil_characters(characters.iter()), 2);
+    }
+}
@@ -0,0 +1,111 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::http::{Http, HttpError, StatusCode};
+use serenity::model::id::*;
+use serenity::Error;
+use tokio::sync::Semaphore;
+
+// How many moves can be in flight at once. Serenity queues anything over
+// Discord's rate limit for us, so this just stops one game flooding it
+const MAX_CONCURRENT_MOVES: usize = 5;
+
+// How many times a move is tried before giving up on it
+const MAX_ATTEMPTS: u32 = 4;
+
+// Wait before the first retry, doubled after each failed attempt
+const FIRST_BACKOFF_MS: u64 = 500;
+
+pub struct MoveRequest {
+    pub user_id: UserId,
+    pub name: String,
+    pub channel_id: ChannelId,
+}
+
+pub struct FailedMove {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Moves every member to their channel concurrently, retrying anything that
+/// failed for a reason that might go away. Returns the moves that never worked
+pub async fn move_members(
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    requests: Vec<MoveRequest>,
+) -> Vec<FailedMove> {
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_MOVES));
+    let mut handles = Vec::new();
+
+    for request in requests {
+        let http = Arc::clone(http);
+        let semaphore = Arc::clone(&semaphore);
+
+        handles.push(tokio::spawn(async move {
+            // The semaphore is never closed, so this can't fail
+            let _permit = semaphore.acquire().await.unwrap();
+
+            let mut backoff = Duration::from_millis(FIRST_BACKOFF_MS);
+            let mut attempt = 1;
+
+            loop {
+                match guild_id
+                    .move_member(&http, request.user_id, request.channel_id)
+                    .await
+                {
+                    Ok(_) => return None,
+                    Err(why) if attempt < MAX_ATTEMPTS && is_transient(&why) => {
+                        tokio::time::sleep(backoff).await;
+
+                        backoff *= 2;
+                        attempt += 1;
+                    }
+                    Err(why) => {
+                        return Some(FailedMove {
+                            name: request.name,
+                            reason: get_reason(&why),
+                        })
+                    }
+                }
+            }
+        }));
+    }
+
+    let mut failures = Vec::new();
+
+    for handle in handles {
+        match handle.await {
+            Ok(Some(failure)) => failures.push(failure),
+            Ok(None) => (),
+            Err(why) => failures.push(FailedMove {
+                name: String::from("Unknown"),
+                reason: format!("Move task stopped: {}", why),
+            }),
+        }
+    }
+
+    failures
+}
+
+// Server errors, rate limits and dropped connections are worth another try,
+// anything else (not in voice, missing permissions) will just fail again
+fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::Http(http_error) => match http_error.status_code() {
+            Some(status) => {
+                status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+            }
+            None => matches!(**http_error, HttpError::Request(_)),
+        },
+        _ => false,
+    }
+}
+
+fn get_reason(error: &Error) -> String {
+    match error {
+        Error::Http(http_error) => match &**http_error {
+            HttpError::UnsuccessfulRequest(response) => response.error.message.clone(),
+            other => other.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,222 @@
+use crate::*;
+
+use serenity::builder::CreateComponents;
+use serenity::model::interactions::{
+    message_component::ButtonStyle, InteractionApplicationCommandCallbackDataFlags,
+};
+
+// The control panel is a single message in the storyteller channel, edited in
+// place after every change so the storyteller always knows where the game is
+
+fn get_panel_description(current_state: &BloodGuild) -> String {
+    let alive = current_state
+        .roles
+        .iter()
+        .filter(|p| current_state.is_alive(p.0))
+        .count();
+
+    format!(
+        "**{}**\n{}\n**Players:** {} alive of {}",
+        current_state.game_state.as_str(),
+        current_state.get_time_str(),
+        alive,
+        current_state.roles.len()
+    )
+}
+
+fn create_panel_buttons(components: &mut CreateComponents) -> &mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id("panel_night")
+                .label("Night")
+                .style(ButtonStyle::Primary)
+        })
+        .create_button(|b| {
+            b.custom_id("panel_day")
+                .label("Day")
+                .style(ButtonStyle::Primary)
+        })
+        .create_button(|b| {
+            b.custom_id("panel_dm")
+                .label("DM roles")
+                .style(ButtonStyle::Secondary)
+        })
+        .create_button(|b| {
+            b.custom_id("panel_edit")
+                .label("Edit")
+                .style(ButtonStyle::Secondary)
+        })
+        .create_button(|b| {
+            b.custom_id("panel_end")
+                .label("End")
+                .style(ButtonStyle::Danger)
+        })
+    })
+}
+
+/// Posts a fresh control panel and remembers it, so it can be edited later
+pub async fn post_control_panel(ctx: &Context, guild_id: u64) {
+    let mut current_state = get_database(&guild_id).await;
+
+    let title = format!("🩸 {}", current_state.game_type.get_name());
+    let description = get_panel_description(&current_state);
+
+    let result = ChannelId(current_state.storyteller_channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| e.title(title).description(description))
+                .components(create_panel_buttons)
+        })
+        .await;
+
+    match result {
+        Ok(message) => {
+            current_state.control_panel = Some(*message.id.as_u64());
+
+            set_database(current_state).await;
+        }
+        Err(why) => print_error(&format!("Could not post control panel: {:?}", why)),
+    }
+}
+
+/// Edits the control panel to show the current state of the game
+pub async fn update_control_panel(ctx: &Context, guild_id: u64) {
+    let current_state = match BLOOD_DATABASE.lock().await.blood_guilds.get(&guild_id) {
+        Some(value) => value.clone(),
+        None => return,
+    };
+
+    let message_id = match current_state.control_panel {
+        Some(value) => value,
+        None => return,
+    };
+
+    let title = format!("🩸 {}", current_state.game_type.get_name());
+    let description = get_panel_description(&current_state);
+
+    if let Err(why) = ChannelId(current_state.storyteller_channel)
+        .edit_message(&ctx.http, message_id, |m| {
+            m.embed(|e| e.title(title).description(description))
+        })
+        .await
+    {
+        print_error(&format!("Could not update control panel: {:?}", why));
+    }
+}
+
+/// Marks the control panel as finished and takes its buttons away
+pub async fn close_control_panel(ctx: &Context, old_state: &BloodGuild) {
+    let message_id = match old_state.control_panel {
+        Some(value) => value,
+        None => return,
+    };
+
+    let title = format!("🩸 {}", old_state.game_type.get_name());
+
+    if let Err(why) = ChannelId(old_state.storyteller_channel)
+        .edit_message(&ctx.http, message_id, |m| {
+            m.embed(|e| e.title(title).description("**Game over!**"))
+                .components(|c| c)
+        })
+        .await
+    {
+        print_error(&format!("Could not close control panel: {:?}", why));
+    }
+}
+
+pub async fn handle_panel_press(ctx: &Context, mut press: ComponentPress, guild_id: u64) {
+    match press.content.as_str() {
+        "panel_night" => {
+            press.content = String::from("night");
+            night(ctx, &press).await;
+        }
+        "panel_day" => {
+            press.content = String::from("day");
+            day(ctx, &press).await;
+        }
+        "panel_dm" => {
+            press.content = String::from("dm");
+            dm_roles(ctx, &press).await;
+        }
+        "panel_edit" => ask_which_seat(ctx, &press, guild_id).await,
+        "panel_edit_seat" => {
+            // The chosen seat number goes on the end, just like "edit 3"
+            let seat = press
+                .interaction
+                .data
+                .values
+                .first()
+                .cloned()
+                .unwrap_or_default();
+
+            press.content = format!("edit {}", seat);
+            edit_role(ctx, &press).await;
+        }
+        "panel_end" => {
+            press.content = String::from("end");
+            print_command(&press);
+
+            end_game(ctx, &press, guild_id).await;
+        }
+        _ => nothing(ctx, &press).await,
+    }
+}
+
+async fn ask_which_seat(ctx: &Context, press: &ComponentPress, guild_id: u64) {
+    let current_state = get_database(&guild_id).await;
+
+    if current_state.roles.is_empty() {
+        send_msg(
+            press,
+            ctx,
+            String::from("**Error:** No roles have been set!"),
+        )
+        .await;
+        return;
+    }
+
+    let mut seats: Vec<(String, String)> = Vec::new();
+
+    for (index, player) in current_state.roles.iter().enumerate() {
+        let character = match &player.2 {
+            Some(value) => value.name.clone(),
+            None => String::from("No role yet"),
+        };
+
+        seats.push((
+            format!(
+                "{}) {} as the {}",
+                index + 1,
+                get_member_name(&player.1),
+                character
+            ),
+            (index + 1).to_string(),
+        ));
+    }
+
+    let result = press
+        .interaction
+        .create_followup_message(&ctx.http, |f| {
+            f.content("**Which player's role do you want to edit?**")
+                .flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_select_menu(|menu| {
+                            menu.custom_id("panel_edit_seat")
+                                .placeholder("Choose a player")
+                                .options(|o| {
+                                    // Discord allows at most 25 options in a menu
+                                    for (label, value) in seats.iter().take(25) {
+                                        o.create_option(|opt| opt.label(label).value(value));
+                                    }
+                                    o
+                                })
+                        })
+                    })
+                })
+        })
+        .await;
+
+    if let Err(why) = result {
+        print_error(&format!("Could not send seat menu: {:?}", why));
+    }
+}
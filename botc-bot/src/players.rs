@@ -0,0 +1,727 @@
+use crate::*;
+
+// Late joiners, dropouts and swaps. Seats are the order of BloodGuild.roles, so
+// everything keyed by user ID (night rooms, deaths, menus) has to follow along
+
+/// The message a player gets telling them their character
+pub fn get_role_message(current_state: &BloodGuild, character: &Character) -> String {
+    // Travelers' alignment is up to the storyteller, so they need to be told it
+    let mut message = if let CharacterType::Traveler = character.char_type {
+        format!(
+            "Your role this game is the **{}**, a **Traveler**. You are **{}**.",
+            character.name,
+            character.alignment.as_str()
+        )
+    } else {
+        match &character.decoy_character {
+            Some(value) => format!(
+                "Your role this game is the **{}**, a **{}**.",
+                value.name, value.char_type_str
+            ),
+            None => format!(
+                "Your role this game is the **{}**, a **{}**.",
+                character.name, character.char_type_str
+            ),
+        }
+    };
+
+    // Only jinxes on the character they think they are, so a Drunk isn't given away
+    let shown_name = match &character.decoy_character {
+        Some(value) => &value.name,
+        None => &character.name,
+    };
+
+    for (other, rule) in current_state.get_active_jinxes(shown_name) {
+        message.push_str(&format!("\n**Jinxed with the {}:** {}", other, rule));
+    }
+
+    message
+}
+
+async fn send_role(
+    ctx: &Context,
+    msg: &impl CommandSource,
+    current_state: &BloodGuild,
+    member: &Member,
+    character: &Character,
+) {
+    let message_to_send = get_role_message(current_state, character);
+
+    send_dm(ctx, msg, member, message_to_send, "their role").await;
+}
+
+/// DMs a player, telling the storyteller if it didn't work
+pub async fn send_dm(
+    ctx: &Context,
+    msg: &impl CommandSource,
+    member: &Member,
+    message_to_send: String,
+    what: &str,
+) -> bool {
+    if let Err(why) = member
+        .user
+        .direct_message(&ctx.http, |m| m.content(&message_to_send))
+        .await
+    {
+        print_error(&format!(
+            "Could not send message to {}: {:?}",
+            member.user.name, why
+        ));
+
+        send_msg(
+            msg,
+            ctx,
+            format!("**Error:** could not send {} {}!", member.user.name, what),
+        )
+        .await;
+
+        return false;
+    }
+
+    true
+}
+
+async fn get_guild_member(ctx: &Context, guild_id: u64, param: Option<&&str>) -> Option<Member> {
+    let user_id = serenity::utils::parse_username(param?)?;
+
+    GuildId(guild_id).member(&ctx.http, user_id).await.ok()
+}
+
+// Undoes everything the bot did to someone who's leaving the game
+async fn release_player(ctx: &Context, current_state: &mut BloodGuild, user_id: u64) {
+    let kind = PermissionOverwriteType::Member(UserId(user_id));
+
+    for (id, overwrites) in &current_state.saved_overwrites {
+        let result = match overwrites.iter().find(|o| o.kind == kind) {
+            Some(previous) => ChannelId(*id).create_permission(&ctx.http, previous).await,
+            None => ChannelId(*id).delete_permission(&ctx.http, kind).await,
+        };
+
+        if let Err(why) = result {
+            print_error(&format!(
+                "Could not unlock {} for {}: {:?}",
+                id, user_id, why
+            ));
+        }
+    }
+
+    // If they aren't in voice, they're unmuted when they next join
+    if current_state.muted.contains(&user_id) && unmute_player(ctx, current_state.id, user_id).await
+    {
+        current_state.muted.remove(&user_id);
+    }
+
+    if let Some(nick) = current_state.original_nicks.remove(&user_id) {
+        let nick = nick.unwrap_or_default();
+
+        if let Err(why) = GuildId(current_state.id)
+            .edit_member(&ctx.http, user_id, |m| m.nickname(&nick))
+            .await
+        {
+            print_error(&format!(
+                "Could not restore nickname of {}: {:?}",
+                user_id, why
+            ));
+        }
+
+        save_original_nicks(current_state.id, &current_state.original_nicks);
+    }
+
+    current_state.night_rooms.remove(&user_id);
+    current_state.dead.remove(&user_id);
+    current_state.used_ghost_votes.remove(&user_id);
+    current_state.role_menus.remove(&user_id);
+    current_state.tokens.remove(&user_id);
+    current_state.notes.remove(&user_id);
+    current_state.night_deaths.retain(|id| *id != user_id);
+    current_state.night_choices.remove(&user_id);
+    current_state.info_log.remove(&user_id);
+}
+
+pub async fn add_player(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let member = match get_guild_member(ctx, *guild_id, params.get(1)).await {
+        Some(value) => value,
+        None => {
+            send_msg(msg, ctx, String::from("Please mention the player to add!")).await;
+            return;
+        }
+    };
+
+    let user_id = *member.user.id.as_u64();
+
+    if current_state.roles.iter().any(|p| p.0 == user_id) {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** They're already in the game!"),
+        )
+        .await;
+        return;
+    }
+
+    // The rest of the message can be their role, like "add @player chef"
+    let role = params.get(2..).map(|r| r.join(" ")).unwrap_or_default();
+
+    let character = if role.is_empty() {
+        None
+    } else {
+        let mut problems: Vec<String> = Vec::new();
+
+        match find_bulk_character(&current_state, &role, &mut problems) {
+            Some(value) => Some(value),
+            None => {
+                send_msg(msg, ctx, format!("**Error:** {}", problems.join("\n"))).await;
+                return;
+            }
+        }
+    };
+
+    let is_playing = matches!(current_state.game_state, GameState::Playing);
+
+    if is_playing && character.is_none() {
+        send_msg(
+            msg,
+            ctx,
+            String::from(
+                "The game has started, so please give them a role too, like \"add @player chef\"!",
+            ),
+        )
+        .await;
+        return;
+    }
+
+    let name = get_member_name(&member);
+    let seat = current_state.roles.len() + 1;
+    let order = current_state.get_night_order();
+
+    current_state
+        .roles
+        .push((user_id, member.clone(), character.clone()));
+
+    if current_state.breaks_spirit_of_ivory() {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** The Spirit of Ivory allows only 1 extra evil player!"),
+        )
+        .await;
+        return;
+    }
+
+    print_info(&format!("User {} joined the game", name));
+    send_msg(msg, ctx, format!("*{}* joined in seat **{}**", name, seat)).await;
+
+    if is_playing {
+        let room = give_night_room(ctx, msg, &mut current_state, user_id, &name).await;
+
+        if let Time::Night = current_state.time {
+            current_state.keep_night_step(&order);
+        }
+
+        lock_new_player(ctx, &current_state, user_id).await;
+
+        // Checked above, everyone joining mid-game has a character
+        send_role(ctx, msg, &current_state, &member, &character.unwrap()).await;
+
+        update_nicknames(ctx, &mut current_state).await;
+
+        // Saved before moving them, so the watchdog knows where they belong
+        set_database(current_state).await;
+
+        move_to_night_room(ctx, msg, *guild_id, &member, room).await;
+
+        update_control_panel(ctx, *guild_id).await;
+
+        update_status_board(ctx, *guild_id).await;
+    } else {
+        // Shows a menu for them, or the role list if they already have a role
+        current_state.game_state = GameState::SettingRoles;
+
+        ask_for_role(ctx, msg, current_state).await;
+    }
+}
+
+// Joining at night, so they need a room of their own
+async fn give_night_room(
+    ctx: &Context,
+    msg: &impl CommandSource,
+    current_state: &mut BloodGuild,
+    user_id: u64,
+    name: &str,
+) -> Option<ChannelId> {
+    if !matches!(current_state.time, Time::Night) {
+        return None;
+    }
+
+    match find_free_night_room(ctx, current_state).await {
+        Some(value) => {
+            current_state.night_rooms.insert(user_id, *value.as_u64());
+            Some(value)
+        }
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                format!("**Error:** There's no free night room for *{}*!", name),
+            )
+            .await;
+            None
+        }
+    }
+}
+
+// Only once the game has been saved, so the watchdog knows where they belong
+async fn move_to_night_room(
+    ctx: &Context,
+    msg: &impl CommandSource,
+    guild_id: u64,
+    member: &Member,
+    room: Option<ChannelId>,
+) {
+    if let Some(room) = room {
+        let moves = vec![MoveRequest {
+            user_id: member.user.id,
+            name: get_member_name(member),
+            channel_id: room,
+        }];
+
+        let failures = move_members(&ctx.http, GuildId(guild_id), moves).await;
+
+        report_failed_moves(ctx, msg, failures).await;
+    }
+}
+
+// The first night room nobody has been given, found the same way "night" finds them
+async fn find_free_night_room(ctx: &Context, current_state: &BloodGuild) -> Option<ChannelId> {
+    let channels = GuildId(current_state.id).channels(&ctx.http).await.ok()?;
+
+    let category = channels
+        .values()
+        .find(|c| c.kind == ChannelType::Category && c.name.to_lowercase().contains("night"))?;
+
+    let mut rooms: Vec<&GuildChannel> = channels
+        .values()
+        .filter(|c| c.kind == ChannelType::Voice && c.category_id == Some(category.id))
+        .collect();
+
+    rooms.sort_by_key(|c| c.position);
+
+    rooms
+        .iter()
+        .map(|c| c.id)
+        .find(|id| !current_state.night_rooms.values().any(|r| r == id.as_u64()))
+}
+
+pub async fn remove_player(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seat = match params.get(1).and_then(|p| current_state.find_seat(p)) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please provide a seat number or mention!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let order = current_state.get_night_order();
+
+    let (user_id, member, _) = current_state.roles.remove(seat);
+    let name = get_member_name(&member);
+
+    // Any of their steps already done tonight no longer count, so nobody is skipped
+    if let Time::Night = current_state.time {
+        current_state.keep_night_step(&order);
+    }
+
+    release_player(ctx, &mut current_state, user_id).await;
+
+    print_info(&format!("User {} left the game", name));
+    send_msg(
+        msg,
+        ctx,
+        format!("Removed *{}*, everyone after them moves up a seat", name),
+    )
+    .await;
+
+    // Seat numbers after them have changed
+    update_nicknames(ctx, &mut current_state).await;
+
+    set_database(current_state).await;
+
+    update_control_panel(ctx, *guild_id).await;
+
+    update_status_board(ctx, *guild_id).await;
+}
+
+pub async fn replace_player(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seat = match params.get(1).and_then(|p| current_state.find_seat(p)) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please provide a seat number or mention, then the new player!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let member = match get_guild_member(ctx, *guild_id, params.get(2)).await {
+        Some(value) => value,
+        None => {
+            send_msg(msg, ctx, String::from("Please mention the new player!")).await;
+            return;
+        }
+    };
+
+    let new_id = *member.user.id.as_u64();
+
+    if current_state.roles.iter().any(|p| p.0 == new_id) {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** They're already in the game!"),
+        )
+        .await;
+        return;
+    }
+
+    let (old_id, old_member, character) = current_state.roles[seat].clone();
+
+    // The new player takes over the seat exactly as it was
+    let night_room = current_state.night_rooms.get(&old_id).copied();
+    let was_dead = current_state.dead.contains(&old_id);
+    let used_vote = current_state.used_ghost_votes.contains(&old_id);
+    let role_menu = current_state.role_menus.get(&old_id).copied();
+    let tokens = current_state.tokens.get(&old_id).cloned();
+    let note = current_state.notes.get(&old_id).cloned();
+    let info = current_state.info_log.get(&old_id).cloned();
+
+    for id in current_state.night_deaths.iter_mut() {
+        if *id == old_id {
+            *id = new_id;
+        }
+    }
+
+    release_player(ctx, &mut current_state, old_id).await;
+
+    if let Some(room) = night_room {
+        current_state.night_rooms.insert(new_id, room);
+    }
+    if was_dead {
+        current_state.dead.insert(new_id);
+    }
+    if used_vote {
+        current_state.used_ghost_votes.insert(new_id);
+    }
+    if let Some(message_id) = role_menu {
+        current_state.role_menus.insert(new_id, message_id);
+    }
+    if let Some(tokens) = tokens {
+        current_state.tokens.insert(new_id, tokens);
+    }
+    if let Some(note) = note {
+        current_state.notes.insert(new_id, note);
+    }
+    if let Some(info) = info {
+        current_state.info_log.insert(new_id, info);
+    }
+
+    let old_name = get_member_name(&old_member);
+    let new_name = get_member_name(&member);
+
+    print_info(&format!("User {} replaced {}", new_name, old_name));
+    send_msg(
+        msg,
+        ctx,
+        format!(
+            "*{}* takes over seat **{}** from *{}*",
+            new_name,
+            seat + 1,
+            old_name
+        ),
+    )
+    .await;
+
+    // Roles are only sent out once the game starts, "dm" will get them otherwise
+    if let (GameState::Playing, Some(value)) = (current_state.game_state, &character) {
+        send_role(ctx, msg, &current_state, &member, value).await;
+    }
+
+    lock_new_player(ctx, &current_state, new_id).await;
+
+    current_state.roles[seat] = (new_id, member, character);
+
+    refresh_role_menu(ctx, &current_state, new_id).await;
+
+    update_nicknames(ctx, &mut current_state).await;
+
+    set_database(current_state).await;
+
+    update_control_panel(ctx, *guild_id).await;
+
+    update_status_board(ctx, *guild_id).await;
+}
+
+pub async fn add_traveler(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    // Like "traveler @player bureaucrat evil"
+    let alignment = match params.last().map(|a| a.to_lowercase()) {
+        Some(value) if value == "good" => Alignment::Good,
+        Some(value) if value == "evil" => Alignment::Evil,
+        _ => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please give a player, a Traveler and good or evil, like \"traveler @player thief evil\"!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let member = match get_guild_member(ctx, *guild_id, params.get(1)).await {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please mention the Traveler's player!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let user_id = *member.user.id.as_u64();
+
+    if current_state.roles.iter().any(|p| p.0 == user_id) {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** They're already in the game!"),
+        )
+        .await;
+        return;
+    }
+
+    let role = params
+        .get(2..params.len() - 1)
+        .map(|r| r.join(" "))
+        .unwrap_or_default();
+
+    let mut problems: Vec<String> = Vec::new();
+
+    let mut character = match find_bulk_character(&current_state, &role, &mut problems) {
+        Some(value) => value,
+        None => {
+            send_msg(msg, ctx, format!("**Error:** {}", problems.join("\n"))).await;
+            return;
+        }
+    };
+
+    if !matches!(character.char_type, CharacterType::Traveler) {
+        send_msg(
+            msg,
+            ctx,
+            format!("**Error:** The {} isn't a Traveler!", character.name),
+        )
+        .await;
+        return;
+    }
+
+    character.alignment = alignment;
+
+    let name = get_member_name(&member);
+    let order = current_state.get_night_order();
+
+    current_state
+        .roles
+        .push((user_id, member.clone(), Some(character.clone())));
+
+    if current_state.breaks_spirit_of_ivory() {
+        send_msg(
+            msg,
+            ctx,
+            String::from("**Error:** The Spirit of Ivory allows only 1 extra evil player!"),
+        )
+        .await;
+        return;
+    }
+
+    print_info(&format!(
+        "User {} joined as the {} {}",
+        name,
+        character.alignment.as_str(),
+        character.name
+    ));
+    send_msg(
+        msg,
+        ctx,
+        format!(
+            "*{}* joined in seat **{}** as the **{}** {}",
+            name,
+            current_state.roles.len(),
+            character.alignment.as_str(),
+            character.name
+        ),
+    )
+    .await;
+
+    let mut room = None;
+
+    // Roles are only sent out once the game starts, "dm" will get them otherwise
+    if let GameState::Playing = current_state.game_state {
+        room = give_night_room(ctx, msg, &mut current_state, user_id, &name).await;
+
+        // They may wake tonight, so the walkthrough has to make room for them
+        if let Time::Night = current_state.time {
+            current_state.keep_night_step(&order);
+        }
+
+        lock_new_player(ctx, &current_state, user_id).await;
+
+        send_role(ctx, msg, &current_state, &member, &character).await;
+    }
+
+    update_nicknames(ctx, &mut current_state).await;
+
+    set_database(current_state).await;
+
+    move_to_night_room(ctx, msg, *guild_id, &member, room).await;
+
+    update_control_panel(ctx, *guild_id).await;
+
+    update_status_board(ctx, *guild_id).await;
+}
+
+pub async fn exile_traveler(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let seat = match params.get(1).and_then(|p| current_state.find_seat(p)) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please provide a seat number or mention!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let user_id = current_state.roles[seat].0;
+    let name = get_member_name(&current_state.roles[seat].1);
+
+    let is_traveler = match &current_state.roles[seat].2 {
+        Some(value) => matches!(value.char_type, CharacterType::Traveler),
+        None => false,
+    };
+
+    if !is_traveler {
+        send_msg(
+            msg,
+            ctx,
+            format!(
+                "**Error:** *{}* isn't a Traveler, so they can't be exiled!",
+                name
+            ),
+        )
+        .await;
+        return;
+    }
+
+    // Everyone counts for exiles, alive or dead, and nobody spends a ghost vote
+    let needed = current_state.roles.len().div_ceil(2);
+
+    let votes = match params.get(2).and_then(|v| v.parse::<usize>().ok()) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                format!(
+                    "*{}* has been called for exile! Everyone can vote, alive or dead, without using their ghost vote. **{}** votes are needed. Type \"exile {} [votes]\" with the count.",
+                    name,
+                    needed,
+                    seat + 1
+                ),
+            )
+            .await;
+            return;
+        }
+    };
+
+    if votes < needed {
+        send_msg(
+            msg,
+            ctx,
+            format!(
+                "*{}* is **not** exiled ({} of {} votes)",
+                name, votes, needed
+            ),
+        )
+        .await;
+        return;
+    }
+
+    current_state.dead.insert(user_id);
+
+    print_info(&format!("User {} was exiled", name));
+    send_msg(
+        msg,
+        ctx,
+        format!("*{}* is **exiled** ({} of {} votes)", name, votes, needed),
+    )
+    .await;
+
+    update_nicknames(ctx, &mut current_state).await;
+
+    set_database(current_state).await;
+
+    update_control_panel(ctx, *guild_id).await;
+
+    update_status_board(ctx, *guild_id).await;
+}
@@ -0,0 +1,305 @@
+use crate::*;
+
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont};
+use tiny_skia::{Color, FillRule, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Transform};
+
+// Draws the town square as a picture: every seat around a circle, like the
+// grimoire on the storyteller's table
+
+pub const FONT_PATH: &str = "fonts/DejaVuSans.ttf";
+
+const SIZE: u32 = 1000;
+const SEAT_RADIUS: f32 = 62.0;
+const TOKEN_RADIUS: f32 = 24.0;
+
+// The storyteller sees everything, while the public picture only shows what
+// the players could see for themselves sitting in the circle
+#[derive(Clone, Copy, PartialEq)]
+pub enum GrimView {
+    Storyteller,
+    Public,
+}
+
+fn get_type_color(char_type: &CharacterType) -> Color {
+    match char_type {
+        CharacterType::Townsfolk => Color::from_rgba8(52, 101, 164, 255),
+        CharacterType::Outsider => Color::from_rgba8(84, 160, 200, 255),
+        CharacterType::Minion => Color::from_rgba8(204, 82, 36, 255),
+        CharacterType::Demon => Color::from_rgba8(170, 20, 30, 255),
+        CharacterType::Traveler => Color::from_rgba8(120, 70, 160, 255),
+        _ => Color::from_rgba8(110, 110, 110, 255),
+    }
+}
+
+fn fill_circle(pixmap: &mut Pixmap, x: f32, y: f32, radius: f32, color: Color) {
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    paint.anti_alias = true;
+
+    if let Some(path) = PathBuilder::from_circle(x, y, radius) {
+        pixmap.fill_path(
+            &path,
+            &paint,
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+}
+
+// Blends text straight into the pixels, centred on (x, y). Characters the
+// font doesn't have, like emoji in nicknames, are left out
+fn draw_text(pixmap: &mut Pixmap, font: &FontVec, text: &str, x: f32, y: f32, size: f32) {
+    let scaled = font.as_scaled(PxScale::from(size));
+
+    let glyph_ids: Vec<_> = text
+        .chars()
+        .map(|c| font.glyph_id(c))
+        .filter(|id| id.0 != 0)
+        .collect();
+
+    let mut width = 0.0;
+    for (index, id) in glyph_ids.iter().enumerate() {
+        if index > 0 {
+            width += scaled.kern(glyph_ids[index - 1], *id);
+        }
+        width += scaled.h_advance(*id);
+    }
+
+    let baseline = y + (scaled.ascent() + scaled.descent()) / 2.0;
+    let mut caret = x - width / 2.0;
+
+    let pixmap_width = pixmap.width() as i32;
+    let pixmap_height = pixmap.height() as i32;
+    let pixels = pixmap.pixels_mut();
+
+    for (index, id) in glyph_ids.iter().enumerate() {
+        if index > 0 {
+            caret += scaled.kern(glyph_ids[index - 1], *id);
+        }
+
+        let glyph = id.with_scale_and_position(size, ab_glyph::point(caret, baseline));
+        caret += scaled.h_advance(*id);
+
+        let outline = match font.outline_glyph(glyph) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        let bounds = outline.px_bounds();
+
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+
+            if px < 0 || py < 0 || px >= pixmap_width || py >= pixmap_height {
+                return;
+            }
+
+            // Text is always white on an opaque picture, so this is a plain mix
+            let pixel = &mut pixels[(py * pixmap_width + px) as usize];
+            let mix = |old: u8| (old as f32 + (255.0 - old as f32) * coverage.min(1.0)) as u8;
+
+            if let Some(value) = PremultipliedColorU8::from_rgba(
+                mix(pixel.red()),
+                mix(pixel.green()),
+                mix(pixel.blue()),
+                255,
+            ) {
+                *pixel = value;
+            }
+        });
+    }
+}
+
+// Seat names without the numbers and skulls the bot adds to nicknames
+fn get_plain_name(current_state: &BloodGuild, user_id: u64, member: &Member) -> String {
+    match current_state.original_nicks.get(&user_id) {
+        Some(Some(value)) => value.clone(),
+        Some(None) => member.user.name.clone(),
+        None => get_member_name(member),
+    }
+}
+
+fn shorten(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let mut short: String = text.chars().take(max - 1).collect();
+    short.push('…');
+    short
+}
+
+/// Renders the grimoire as a PNG, or an error message for the storyteller
+pub fn render_grimoire(current_state: &BloodGuild, view: GrimView) -> Result<Vec<u8>, String> {
+    let font_data = std::fs::read(FONT_PATH)
+        .map_err(|why| format!("Could not read the font at {}: {}", FONT_PATH, why))?;
+    let font = FontVec::try_from_vec(font_data)
+        .map_err(|_| format!("The font at {} isn't a valid font", FONT_PATH))?;
+
+    let mut pixmap = Pixmap::new(SIZE, SIZE).ok_or("Could not create the image")?;
+    pixmap.fill(Color::from_rgba8(28, 24, 30, 255));
+
+    let centre = SIZE as f32 / 2.0;
+    let circle_radius = centre - SEAT_RADIUS - 40.0;
+    let seats = current_state.roles.len().max(1) as f32;
+
+    for (seat, (user_id, member, character)) in current_state.roles.iter().enumerate() {
+        // Seat 1 sits at the top, and the rest go round clockwise
+        let angle = std::f32::consts::TAU * seat as f32 / seats - std::f32::consts::FRAC_PI_2;
+        let x = centre + circle_radius * angle.cos();
+        let y = centre + circle_radius * angle.sin();
+
+        let is_traveler = matches!(
+            character.as_ref().map(|c| &c.char_type),
+            Some(CharacterType::Traveler)
+        );
+
+        // Travelers are public knowledge, every other character is hidden
+        let shown = match view {
+            GrimView::Storyteller => character.as_ref(),
+            GrimView::Public if is_traveler => character.as_ref(),
+            GrimView::Public => None,
+        };
+
+        let color = match shown {
+            Some(value) => get_type_color(&value.char_type),
+            None => Color::from_rgba8(90, 80, 70, 255),
+        };
+
+        fill_circle(&mut pixmap, x, y, SEAT_RADIUS, color);
+
+        if let Some(value) = shown {
+            draw_text(
+                &mut pixmap,
+                &font,
+                &shorten(&value.name, 14),
+                x,
+                y - 8.0,
+                17.0,
+            );
+
+            if view == GrimView::Storyteller {
+                let believed = match &value.decoy_character {
+                    Some(decoy) => format!("thinks {}", shorten(&decoy.name, 10)),
+                    None => value.alignment.as_str().to_string(),
+                };
+
+                draw_text(&mut pixmap, &font, &believed, x, y + 14.0, 13.0);
+            }
+        }
+
+        // A shroud over the dead, with a white dot while they still have their ghost vote
+        let alive = match view {
+            GrimView::Storyteller => current_state.is_alive(*user_id),
+            GrimView::Public => current_state.is_publicly_alive(*user_id),
+        };
+
+        if !alive {
+            fill_circle(
+                &mut pixmap,
+                x,
+                y,
+                SEAT_RADIUS,
+                Color::from_rgba8(0, 0, 0, 150),
+            );
+            draw_text(&mut pixmap, &font, "DEAD", x, y + 34.0, 15.0);
+
+            if !current_state.used_ghost_votes.contains(user_id) {
+                fill_circle(
+                    &mut pixmap,
+                    x + SEAT_RADIUS * 0.7,
+                    y - SEAT_RADIUS * 0.7,
+                    10.0,
+                    Color::WHITE,
+                );
+            }
+        }
+
+        let name = format!(
+            "{}. {}",
+            seat + 1,
+            shorten(&get_plain_name(current_state, *user_id, member), 16)
+        );
+        draw_text(&mut pixmap, &font, &name, x, y + SEAT_RADIUS + 14.0, 16.0);
+
+        // Reminder tokens sit between the seat and the middle of the circle
+        if view == GrimView::Storyteller {
+            if let Some(tokens) = current_state.tokens.get(user_id) {
+                for (index, token) in tokens.iter().enumerate() {
+                    let distance = SEAT_RADIUS
+                        + TOKEN_RADIUS
+                        + 8.0
+                        + index as f32 * (TOKEN_RADIUS * 2.0 + 6.0);
+                    let tx = x - distance * angle.cos();
+                    let ty = y - distance * angle.sin();
+
+                    fill_circle(
+                        &mut pixmap,
+                        tx,
+                        ty,
+                        TOKEN_RADIUS,
+                        Color::from_rgba8(220, 210, 190, 255),
+                    );
+                    fill_circle(
+                        &mut pixmap,
+                        tx,
+                        ty,
+                        TOKEN_RADIUS - 3.0,
+                        Color::from_rgba8(60, 50, 50, 255),
+                    );
+                    draw_text(&mut pixmap, &font, &shorten(&token.text, 9), tx, ty, 10.0);
+                }
+            }
+        }
+    }
+
+    draw_text(
+        &mut pixmap,
+        &font,
+        &current_state.get_time_str(),
+        centre,
+        centre - 20.0,
+        26.0,
+    );
+
+    let alive = current_state
+        .roles
+        .iter()
+        .filter(|p| match view {
+            GrimView::Storyteller => current_state.is_alive(p.0),
+            GrimView::Public => current_state.is_publicly_alive(p.0),
+        })
+        .count();
+
+    draw_text(
+        &mut pixmap,
+        &font,
+        &format!("{} alive of {}", alive, current_state.roles.len()),
+        centre,
+        centre + 14.0,
+        20.0,
+    );
+
+    if !current_state.fabled.is_empty() {
+        let fabled: Vec<&str> = current_state
+            .fabled
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+
+        draw_text(
+            &mut pixmap,
+            &font,
+            &format!("Fabled: {}", fabled.join(", ")),
+            centre,
+            centre + 46.0,
+            16.0,
+        );
+    }
+
+    pixmap
+        .encode_png()
+        .map_err(|why| format!("Could not encode the image: {}", why))
+}
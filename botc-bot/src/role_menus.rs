@@ -0,0 +1,340 @@
+use crate::*;
+
+use serenity::builder::CreateComponents;
+
+// Each player gets one message in the storyteller channel with a select menu
+// per character type. Picking a Decoy (Drunk, Lunatic) swaps the menus for a
+// second set to choose who they believe they are
+
+/// Whether a player still needs a character, or a believed one for a Decoy
+pub fn needs_role(character: &Option<Character>) -> bool {
+    match character {
+        Some(value) => value.char_type_str == "Decoy" && value.decoy_character.is_none(),
+        None => true,
+    }
+}
+
+// Groups the script's characters into one list per menu, in the usual order.
+// Decoys really are Outsiders, so they go in with them
+fn group_characters(game_type: &GameType, include_decoys: bool) -> Vec<(String, Vec<Character>)> {
+    let mut groups: Vec<(String, Vec<Character>)> = Vec::new();
+
+    for char_type in &["Townsfolk", "Outsider", "Minion", "Demon"] {
+        let mut characters: Vec<Character> = game_type
+            .get_all_characters()
+            .into_iter()
+            .filter(|c| {
+                c.char_type_str == *char_type
+                    || (include_decoys && *char_type == "Outsider" && c.char_type_str == "Decoy")
+            })
+            .collect();
+
+        characters.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if !characters.is_empty() {
+            groups.push((String::from(*char_type), characters));
+        }
+    }
+
+    groups
+}
+
+fn create_character_menus<'a>(
+    components: &'a mut CreateComponents,
+    prefix: &str,
+    user_id: u64,
+    groups: &[(String, Vec<Character>)],
+) -> &'a mut CreateComponents {
+    // Discord allows 5 rows per message and 25 options per menu
+    for (index, (label, characters)) in groups.iter().enumerate().take(5) {
+        components.create_action_row(|row| {
+            row.create_select_menu(|menu| {
+                menu.custom_id(format!("{}_{}_{}", prefix, index, user_id))
+                    .placeholder(label)
+                    .options(|o| {
+                        for character in characters.iter().take(25) {
+                            o.create_option(|opt| {
+                                opt.label(&character.name).value(&character.name)
+                            });
+                        }
+                        o
+                    })
+            })
+        });
+    }
+
+    components
+}
+
+/// Posts a menu for every player who needs one and doesn't have one yet
+pub async fn post_role_menus(ctx: &Context, current_state: &mut BloodGuild) {
+    for player in current_state.roles.clone() {
+        if !needs_role(&player.2) || current_state.role_menus.contains_key(&player.0) {
+            continue;
+        }
+
+        let (content, prefix, groups) = get_menu_parts(current_state, &player.1, &player.2);
+
+        let result = ChannelId(current_state.storyteller_channel)
+            .send_message(&ctx.http, |m| {
+                m.content(content)
+                    .components(|c| create_character_menus(c, prefix, player.0, &groups))
+            })
+            .await;
+
+        match result {
+            Ok(message) => {
+                current_state
+                    .role_menus
+                    .insert(player.0, *message.id.as_u64());
+            }
+            Err(why) => print_error(&format!("Could not send role menu: {:?}", why)),
+        }
+    }
+}
+
+fn get_menu_parts(
+    current_state: &BloodGuild,
+    member: &Member,
+    character: &Option<Character>,
+) -> (String, &'static str, Vec<(String, Vec<Character>)>) {
+    let name = get_member_name(member);
+
+    match character {
+        Some(value) => (
+            format!(
+                "*{}* is the **{}**. **Who do they believe they are?**",
+                name, value.name
+            ),
+            "believed",
+            group_characters(&current_state.game_type, false),
+        ),
+        None => (
+            format!("**Choose a role** for *{}*", name),
+            "role",
+            group_characters(&current_state.game_type, true),
+        ),
+    }
+}
+
+/// Edits a player's menu to match their role, removing it once they're done
+pub async fn refresh_role_menu(ctx: &Context, current_state: &BloodGuild, user_id: u64) {
+    let message_id = match current_state.role_menus.get(&user_id) {
+        Some(value) => *value,
+        None => return,
+    };
+
+    let player = match current_state.roles.iter().find(|p| p.0 == user_id) {
+        Some(value) => value,
+        None => return,
+    };
+
+    let result = if needs_role(&player.2) {
+        let (content, prefix, groups) = get_menu_parts(current_state, &player.1, &player.2);
+
+        ChannelId(current_state.storyteller_channel)
+            .edit_message(&ctx.http, message_id, |m| {
+                m.content(content)
+                    .components(|c| create_character_menus(c, prefix, user_id, &groups))
+            })
+            .await
+    } else {
+        let character = player.2.as_ref().unwrap();
+
+        let content = match &character.decoy_character {
+            Some(decoy) => format!(
+                "*{}* is the **{}** and believes they are the **{}**",
+                get_member_name(&player.1),
+                character.name,
+                decoy.name
+            ),
+            None => format!(
+                "*{}* is the **{}**",
+                get_member_name(&player.1),
+                character.name
+            ),
+        };
+
+        ChannelId(current_state.storyteller_channel)
+            .edit_message(&ctx.http, message_id, |m| {
+                m.content(content).components(|c| c)
+            })
+            .await
+    };
+
+    if let Err(why) = result {
+        print_error(&format!("Could not update role menu: {:?}", why));
+    }
+}
+
+/// Asks which of several equally good matches the storyteller meant
+pub async fn ask_to_pick_role(
+    ctx: &Context,
+    current_state: &BloodGuild,
+    user_id: u64,
+    believed: bool,
+    choices: Vec<Character>,
+) {
+    let name = match current_state.roles.iter().find(|p| p.0 == user_id) {
+        Some(value) => get_member_name(&value.1),
+        None => return,
+    };
+
+    let prefix = if believed { "believed" } else { "role" };
+
+    let result = ChannelId(current_state.storyteller_channel)
+        .send_message(&ctx.http, |m| {
+            m.content(format!("**Which did you mean** for *{}*?", name))
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_select_menu(|menu| {
+                            menu.custom_id(format!("{}_pick_{}", prefix, user_id))
+                                .placeholder("Choose a character")
+                                .options(|o| {
+                                    for character in choices.iter().take(25) {
+                                        o.create_option(|opt| {
+                                            opt.label(&character.name)
+                                                .value(&character.name)
+                                                .description(character.char_type.as_str())
+                                        });
+                                    }
+                                    o
+                                })
+                        })
+                    })
+                })
+        })
+        .await;
+
+    if let Err(why) = result {
+        print_error(&format!("Could not send role choices: {:?}", why));
+    }
+}
+
+// Sets one player's character straight in the live game, so two quick picks
+// don't undo each other, and returns the game as it is afterwards. The pick
+// that leaves nobody waiting moves the game on, so later picks are turned away
+async fn set_live_role(
+    guild_id: u64,
+    user_id: u64,
+    character_name: &str,
+    is_believed: bool,
+) -> Result<BloodGuild, &'static str> {
+    let mut lock = BLOOD_DATABASE.lock().await;
+
+    let current_state = lock
+        .blood_guilds
+        .get_mut(&guild_id)
+        .ok_or("No game is running!")?;
+
+    if !matches!(current_state.game_state, GameState::SettingRoles) {
+        return Err("Roles aren't being assigned right now!");
+    }
+
+    let seat = current_state
+        .roles
+        .iter()
+        .position(|p| p.0 == user_id)
+        .ok_or("That player isn't in the game!")?;
+
+    let chosen = current_state
+        .game_type
+        .get_character(character_name)
+        .ok_or("That character isn't on the script!")?;
+
+    let name = get_member_name(&current_state.roles[seat].1);
+
+    if is_believed {
+        let real_char = current_state.roles[seat]
+            .2
+            .clone()
+            .ok_or("That player doesn't have a role yet!")?;
+
+        print_info(&format!(
+            "User {}'s decoy role is the {}",
+            name, chosen.name
+        ));
+
+        current_state.roles[seat].2 = Some(Character::add_decoy(real_char, chosen.to_decoy()));
+    } else {
+        print_info(&format!("User {} is role {}", name, chosen.name));
+
+        current_state.roles[seat].2 = Some(chosen);
+    }
+
+    if !current_state.roles.iter().any(|p| needs_role(&p.2)) {
+        current_state.game_state = GameState::SettingUp;
+    }
+
+    Ok(current_state.clone())
+}
+
+pub async fn handle_role_menu(ctx: &Context, press: ComponentPress, guild_id: u64) {
+    // Custom IDs look like "role_<menu>_<user id>"
+    let is_believed = press.content.starts_with("believed_");
+
+    let user_id = match press
+        .content
+        .rsplit('_')
+        .next()
+        .and_then(|id| id.parse::<u64>().ok())
+    {
+        Some(value) => value,
+        None => return,
+    };
+
+    let character_name = press
+        .interaction
+        .data
+        .values
+        .first()
+        .cloned()
+        .unwrap_or_default();
+
+    let result = set_live_role(guild_id, user_id, &character_name, is_believed).await;
+
+    let mut current_state = match result {
+        Ok(value) => value,
+        Err(why) => {
+            send_msg(&press, ctx, format!("**Error:** {}", why)).await;
+            return;
+        }
+    };
+
+    let name = current_state
+        .roles
+        .iter()
+        .find(|p| p.0 == user_id)
+        .map(|p| get_member_name(&p.1))
+        .unwrap_or_default();
+
+    refresh_role_menu(ctx, &current_state, user_id).await;
+
+    // A "which did you mean" menu is only good for one answer
+    if press.content.contains("_pick_") {
+        let content = format!("Picked the **{}** for *{}*", character_name, name);
+
+        if let Err(why) = press
+            .interaction
+            .edit_original_interaction_response(&ctx.http, |r| r.content(content).components(|c| c))
+            .await
+        {
+            print_error(&format!("Could not update role choices: {:?}", why));
+        }
+    }
+
+    if matches!(current_state.game_state, GameState::SettingUp) {
+        ask_for_role(ctx, &press, current_state).await;
+        return;
+    }
+
+    // Picking a Decoy needs a believed character too, so a new menu may be
+    // needed. Only the menus are saved, leaving anyone else's picks alone
+    post_role_menus(ctx, &mut current_state).await;
+
+    if let Some(value) = BLOOD_DATABASE.lock().await.blood_guilds.get_mut(&guild_id) {
+        for (player, message) in current_state.role_menus {
+            value.role_menus.entry(player).or_insert(message);
+        }
+    }
+}
@@ -0,0 +1,547 @@
+use crate::*;
+
+use serenity::builder::CreateApplicationCommands;
+use serenity::model::interactions::{
+    application_command::*, autocomplete::*, message_component::*, Interaction,
+    InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// A slash command, along with the text command it stands in for. Replies are
+// sent as private followups, so only the storyteller who used it sees them
+pub struct SlashCommand {
+    pub interaction: ApplicationCommandInteraction,
+    pub content: String,
+}
+
+#[async_trait]
+impl CommandSource for SlashCommand {
+    fn guild_id(&self) -> Option<GuildId> {
+        self.interaction.guild_id
+    }
+
+    fn author(&self) -> &User {
+        &self.interaction.user
+    }
+
+    fn member_roles(&self) -> Option<&Vec<RoleId>> {
+        self.interaction.member.as_ref().map(|m| &m.roles)
+    }
+
+    fn content(&self) -> &str {
+        &self.content
+    }
+
+    async fn say(&self, ctx: &Context, content: String) -> serenity::Result<()> {
+        self.interaction
+            .create_followup_message(&ctx.http, |f| {
+                f.content(content)
+                    .flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+            })
+            .await
+            .map(|_| ())
+    }
+
+    async fn say_embed(
+        &self,
+        ctx: &Context,
+        title: String,
+        description: String,
+        footer: Option<String>,
+    ) -> serenity::Result<()> {
+        self.interaction
+            .create_followup_message(&ctx.http, |f| {
+                f.create_embed(|e| {
+                    e.title(title);
+                    e.description(description);
+                    if let Some(footer) = footer {
+                        e.footer(|f| f.text(footer));
+                    }
+                    e
+                })
+                .flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+// A button press or menu choice, along with the text command it stands in for.
+// Like slash commands, replies only go to the storyteller who pressed it
+pub struct ComponentPress {
+    pub interaction: MessageComponentInteraction,
+    pub content: String,
+}
+
+#[async_trait]
+impl CommandSource for ComponentPress {
+    fn guild_id(&self) -> Option<GuildId> {
+        self.interaction.guild_id
+    }
+
+    fn author(&self) -> &User {
+        &self.interaction.user
+    }
+
+    fn member_roles(&self) -> Option<&Vec<RoleId>> {
+        self.interaction.member.as_ref().map(|m| &m.roles)
+    }
+
+    fn content(&self) -> &str {
+        &self.content
+    }
+
+    async fn say(&self, ctx: &Context, content: String) -> serenity::Result<()> {
+        self.interaction
+            .create_followup_message(&ctx.http, |f| {
+                f.content(content)
+                    .flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+            })
+            .await
+            .map(|_| ())
+    }
+
+    async fn say_embed(
+        &self,
+        ctx: &Context,
+        title: String,
+        description: String,
+        footer: Option<String>,
+    ) -> serenity::Result<()> {
+        self.interaction
+            .create_followup_message(&ctx.http, |f| {
+                f.create_embed(|e| {
+                    e.title(title);
+                    e.description(description);
+                    if let Some(footer) = footer {
+                        e.footer(|f| f.text(footer));
+                    }
+                    e
+                })
+                .flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+// Set once the commands are registered, as "ready" comes again on every reconnect
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Registers every slash command globally, offering the loaded games as choices for /start
+pub async fn register_commands(ctx: &Context) {
+    // Without the application, there's nothing to register them to
+    if ctx.http.application_id == 0 || REGISTERED.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let game_names: Vec<String> = BLOOD_DATABASE
+        .lock()
+        .await
+        .games
+        .iter()
+        .map(|g| g.get_name())
+        .collect();
+
+    let result = ApplicationCommand::set_global_application_commands(&ctx.http, |commands| {
+        create_commands(commands, &game_names)
+    })
+    .await;
+
+    match result {
+        Ok(commands) => {
+            REGISTERED.store(true, Ordering::SeqCst);
+
+            print_info(&format!("Registered {} slash commands", commands.len()))
+        }
+        Err(why) => print_error(&format!("Could not register slash commands: {:?}", why)),
+    }
+}
+
+fn create_commands<'a>(
+    commands: &'a mut CreateApplicationCommands,
+    game_names: &[String],
+) -> &'a mut CreateApplicationCommands {
+    commands
+        .create_application_command(|c| {
+            c.name("start")
+                .description("Start a new game, bound to this channel")
+                .create_option(|o| {
+                    o.name("game")
+                        .description("The script to play")
+                        .kind(ApplicationCommandOptionType::String)
+                        .required(true);
+
+                    for name in game_names {
+                        o.add_string_choice(name, name);
+                    }
+
+                    o
+                })
+        })
+        .create_application_command(|c| c.name("end").description("End the current game"))
+        .create_application_command(|c| {
+            c.name("roles")
+                .description("Collect the players in your voice channel and assign their roles")
+        })
+        .create_application_command(|c| {
+            c.name("edit")
+                .description("Set a player's character")
+                .create_option(|o| {
+                    o.name("player")
+                        .description("The player to edit")
+                        .kind(ApplicationCommandOptionType::User)
+                        .required(true)
+                })
+                .create_option(|o| {
+                    o.name("character")
+                        .description("Their true character")
+                        .kind(ApplicationCommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+                .create_option(|o| {
+                    o.name("believes")
+                        .description("The character they think they are, for the Drunk or Lunatic")
+                        .kind(ApplicationCommandOptionType::String)
+                        .set_autocomplete(true)
+                })
+        })
+        .create_application_command(|c| c.name("dm").description("DM every player their role"))
+        .create_application_command(|c| {
+            c.name("night")
+                .description("Send everyone to their night rooms")
+        })
+        .create_application_command(|c| c.name("day").description("Wake everyone up"))
+}
+
+pub async fn handle_interaction(ctx: &Context, interaction: Interaction) {
+    match interaction {
+        Interaction::ApplicationCommand(command) => handle_command(ctx, command).await,
+        Interaction::Autocomplete(autocomplete) => suggest_characters(ctx, autocomplete).await,
+        Interaction::MessageComponent(component) => handle_component(ctx, component).await,
+        _ => (),
+    }
+}
+
+async fn handle_command(ctx: &Context, interaction: ApplicationCommandInteraction) {
+    // Defer straight away, as moving players can take longer than Discord waits for a reply
+    if let Err(why) = interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|d| {
+                    d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                })
+        })
+        .await
+    {
+        print_error(&format!("Could not respond to slash command: {:?}", why));
+        return;
+    }
+
+    let command = SlashCommand {
+        content: interaction.data.name.clone(),
+        interaction,
+    };
+
+    let guild_id = match command.guild_id() {
+        Some(value) => *value.as_u64(),
+        None => {
+            send_msg(
+                &command,
+                ctx,
+                String::from("**Error:** Games can only be run in a server!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    if !has_discord_role(ctx, &command, "storytell").await {
+        send_msg(
+            &command,
+            ctx,
+            String::from("**Error:** Only storytellers can do that!"),
+        )
+        .await;
+        return;
+    }
+
+    if command.content == "start" {
+        let game_name = get_string_option(&command.interaction, "game").unwrap_or_default();
+
+        let game_type = BLOOD_DATABASE
+            .lock()
+            .await
+            .games
+            .iter()
+            .find(|g| g.get_name() == game_name)
+            .cloned();
+
+        match game_type {
+            Some(game_type) => {
+                print_command(&command);
+
+                let channel_id = *command.interaction.channel_id.as_u64();
+
+                create_game(ctx, &command, guild_id, channel_id, game_type).await;
+            }
+            None => {
+                send_msg(
+                    &command,
+                    ctx,
+                    format!("**Error:** No game called {}", game_name),
+                )
+                .await
+            }
+        }
+
+        return;
+    }
+
+    if !BLOOD_DATABASE
+        .lock()
+        .await
+        .blood_guilds
+        .contains_key(&guild_id)
+    {
+        send_msg(
+            &command,
+            ctx,
+            String::from("**Error:** No game is running! Use /start first."),
+        )
+        .await;
+        return;
+    }
+
+    match command.content.as_str() {
+        "end" => {
+            print_command(&command);
+
+            end_game(ctx, &command, guild_id).await;
+        }
+        "roles" => roles(ctx, &command).await,
+        "edit" => edit_character(ctx, &command).await,
+        "dm" => dm_roles(ctx, &command).await,
+        "night" => night(ctx, &command).await,
+        "day" => day(ctx, &command).await,
+        _ => nothing(ctx, &command).await,
+    }
+}
+
+async fn handle_component(ctx: &Context, interaction: MessageComponentInteraction) {
+    // Acknowledge without changing the message, replies are sent as followups
+    if let Err(why) = interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::DeferredUpdateMessage)
+        })
+        .await
+    {
+        print_error(&format!("Could not respond to component: {:?}", why));
+        return;
+    }
+
+    let press = ComponentPress {
+        content: interaction.data.custom_id.clone(),
+        interaction,
+    };
+
+    // Night choices are made by players in their DMs
+    if press.content.starts_with("choice_") {
+        handle_choice(ctx, press).await;
+        return;
+    }
+
+    // So are answers to whisper requests
+    if press.content.starts_with("whisper_") {
+        handle_whisper_press(ctx, press).await;
+        return;
+    }
+
+    let guild_id = match press.guild_id() {
+        Some(value) => *value.as_u64(),
+        None => return,
+    };
+
+    if !has_discord_role(ctx, &press, "storytell").await {
+        send_msg(&press, ctx, String::from("**Error:** Only storytellers can do that!")).await;
+        return;
+    }
+
+    if !BLOOD_DATABASE.lock().await.blood_guilds.contains_key(&guild_id) {
+        send_msg(&press, ctx, String::from("**Error:** No game is running!")).await;
+        return;
+    }
+
+    if press.content.starts_with("panel_") {
+        handle_panel_press(ctx, press, guild_id).await;
+    } else if press.content.starts_with("role_") || press.content.starts_with("believed_") {
+        handle_role_menu(ctx, press, guild_id).await;
+    } else if press.content.starts_with("dawn_") {
+        handle_dawn_press(ctx, press).await;
+    }
+}
+
+async fn edit_character(ctx: &Context, command: &SlashCommand) {
+    print_command(command);
+
+    let guild_id = &command.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    if let GameState::Playing = current_state.game_state {
+        send_msg(command, ctx, String::from("Cannot edit roles in-game!")).await;
+        return;
+    }
+
+    let user_id = get_user_option(&command.interaction, "player");
+
+    let seat = match user_id.and_then(|u| current_state.roles.iter().position(|p| p.0 == u)) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                command,
+                ctx,
+                String::from("**Error:** That player isn't in the game! Use /roles first."),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let character_name = get_string_option(&command.interaction, "character").unwrap_or_default();
+
+    let mut character = match find_unique_character(command, ctx, &current_state, &character_name)
+        .await
+    {
+        Some(value) => value,
+        None => return,
+    };
+
+    if let Some(believes) = get_string_option(&command.interaction, "believes") {
+        match find_unique_character(command, ctx, &current_state, &believes).await {
+            Some(value) => character = Character::add_decoy(character, value.to_decoy()),
+            None => return,
+        }
+    }
+
+    print_info(&format!(
+        "User {} is role {}",
+        current_state.roles[seat].1.user.name, character.name
+    ));
+
+    current_state.roles[seat].2 = Some(character);
+
+    let user_id = current_state.roles[seat].0;
+    refresh_role_menu(ctx, &current_state, user_id).await;
+
+    // Carry on prompting for anyone left, or show the finished role list
+    current_state.game_state = GameState::SettingRoles;
+
+    ask_for_role(ctx, command, current_state).await;
+}
+
+// Looks up what the storyteller typed, explaining what went wrong if it isn't one character
+async fn find_unique_character(
+    command: &SlashCommand,
+    ctx: &Context,
+    current_state: &BloodGuild,
+    input: &str,
+) -> Option<Character> {
+    match current_state.game_type.find_character(input) {
+        CharacterMatch::Found(character) => Some(character),
+        CharacterMatch::Ambiguous(choices) => {
+            let names: Vec<String> = choices.iter().map(|c| format!("**{}**", c.name)).collect();
+
+            send_msg(
+                command,
+                ctx,
+                format!("Did you mean {}? Please try again!", names.join(" or ")),
+            )
+            .await;
+            None
+        }
+        CharacterMatch::NotFound => {
+            send_msg(
+                command,
+                ctx,
+                format!("Could not find role {} in current game. Please try again!", input),
+            )
+            .await;
+            None
+        }
+    }
+}
+
+async fn suggest_characters(ctx: &Context, autocomplete: AutocompleteInteraction) {
+    let typed = autocomplete
+        .data
+        .options
+        .iter()
+        .find(|o| o.focused)
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let game_type = match autocomplete.guild_id {
+        Some(guild_id) => BLOOD_DATABASE
+            .lock()
+            .await
+            .blood_guilds
+            .get(guild_id.as_u64())
+            .map(|g| g.game_type.clone()),
+        None => None,
+    };
+
+    let mut names: Vec<String> = match game_type {
+        Some(game_type) => game_type
+            .get_all_characters()
+            .into_iter()
+            .map(|c| c.name)
+            .filter(|n| n.to_lowercase().contains(&typed))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    names.sort();
+
+    if let Err(why) = autocomplete
+        .create_autocomplete_response(&ctx.http, |r| {
+            // Discord shows at most 25 choices
+            for name in names.iter().take(25) {
+                r.add_string_choice(name, name);
+            }
+            r
+        })
+        .await
+    {
+        print_error(&format!("Could not send autocomplete: {:?}", why));
+    }
+}
+
+fn get_string_option(interaction: &ApplicationCommandInteraction, name: &str) -> Option<String> {
+    interaction
+        .data
+        .options
+        .iter()
+        .find(|o| o.name == name)
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .map(String::from)
+}
+
+fn get_user_option(interaction: &ApplicationCommandInteraction, name: &str) -> Option<u64> {
+    match interaction
+        .data
+        .options
+        .iter()
+        .find(|o| o.name == name)
+        .and_then(|o| o.resolved.as_ref())
+    {
+        Some(ApplicationCommandInteractionDataOptionValue::User(user, _)) => {
+            Some(*user.id.as_u64())
+        }
+        _ => None,
+    }
+}
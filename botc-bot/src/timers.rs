@@ -0,0 +1,428 @@
+use crate::*;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// Day timers for open discussion, nominations and speeches. The countdown is
+// posted in the public channel and edited as it runs down, and the
+// storyteller is warned before it ends. Only one timer runs at a time
+
+// How often the countdown message is edited, in seconds
+const TICK: u64 = 15;
+
+// When the storyteller gets their warning, in seconds left
+const WARNING: u64 = 60;
+
+// Shared by every game, so a timer left over from an ended game can never
+// match one in a new game. Starts at 1, as no timer is ever given 0
+static NEXT_TIMER: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone)]
+pub struct DayTimers {
+    pub discussion: u64,
+    pub nominations: u64,
+    pub speech: u64,
+    pub auto_night: bool,
+    // Changed whenever a timer starts or stops, so older timers know they're finished
+    pub running: u64,
+}
+
+impl DayTimers {
+    pub fn new() -> Self {
+        DayTimers {
+            discussion: 5 * 60,
+            nominations: 3 * 60,
+            speech: 60,
+            auto_night: false,
+            running: 0,
+        }
+    }
+
+    fn get_length(&self, kind: &str) -> Option<u64> {
+        match kind {
+            "discussion" => Some(self.discussion),
+            "nominations" => Some(self.nominations),
+            "speech" => Some(self.speech),
+            _ => None,
+        }
+    }
+
+    fn set_length(&mut self, kind: &str, length: u64) -> bool {
+        match kind {
+            "discussion" => self.discussion = length,
+            "nominations" => self.nominations = length,
+            "speech" => self.speech = length,
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+fn get_timer_title(kind: &str) -> &str {
+    match kind {
+        "discussion" => "Open discussion",
+        "nominations" => "Nominations",
+        _ => "Speech",
+    }
+}
+
+/// Takes "5" as minutes, "1:30" as minutes and seconds, or "90s" as seconds
+fn parse_length(input: &str) -> Option<u64> {
+    if let Some(seconds) = input.strip_suffix('s') {
+        return seconds.parse::<u64>().ok().filter(|s| *s > 0);
+    }
+
+    // Checked, so a huge number is turned down instead of overflowing
+    let length = match input.split_once(':') {
+        Some((minutes, seconds)) => {
+            let seconds = seconds.parse::<u64>().ok().filter(|s| *s < 60)?;
+
+            minutes
+                .parse::<u64>()
+                .ok()?
+                .checked_mul(60)?
+                .checked_add(seconds)?
+        }
+        None => input.parse::<u64>().ok()?.checked_mul(60)?,
+    };
+
+    Some(length).filter(|l| *l > 0)
+}
+
+fn format_length(length: u64) -> String {
+    format!("{}:{:02}", length / 60, length % 60)
+}
+
+// Lets the timer run "night" itself once it's over, replying in the storyteller channel
+struct TimerSource {
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    author: User,
+}
+
+#[async_trait]
+impl CommandSource for TimerSource {
+    fn guild_id(&self) -> Option<GuildId> {
+        Some(self.guild_id)
+    }
+
+    fn author(&self) -> &User {
+        &self.author
+    }
+
+    fn member_roles(&self) -> Option<&Vec<RoleId>> {
+        None
+    }
+
+    fn content(&self) -> &str {
+        "night"
+    }
+
+    async fn say(&self, ctx: &Context, content: String) -> serenity::Result<()> {
+        self.channel_id.say(&ctx.http, content).await.map(|_| ())
+    }
+
+    async fn say_embed(
+        &self,
+        ctx: &Context,
+        title: String,
+        description: String,
+        footer: Option<String>,
+    ) -> serenity::Result<()> {
+        self.channel_id
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    e.title(title);
+                    e.description(description);
+                    if let Some(footer) = footer {
+                        e.footer(|f| f.text(footer));
+                    }
+                    e
+                })
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+// Whether this timer is still the one running, and the game hasn't ended
+async fn is_running(guild_id: u64, running: u64) -> bool {
+    match BLOOD_DATABASE.lock().await.blood_guilds.get(&guild_id) {
+        Some(value) => value.timers.running == running,
+        None => false,
+    }
+}
+
+async fn run_timer(ctx: Context, source: TimerSource, kind: String, length: u64, running: u64) {
+    let guild_id = *source.guild_id.as_u64();
+
+    let current_state = get_database(&guild_id).await;
+
+    let public = current_state
+        .public_channel
+        .unwrap_or(current_state.storyteller_channel);
+    let title = get_timer_title(&kind).to_string();
+
+    let countdown = match ChannelId(public)
+        .say(
+            &ctx.http,
+            format!("⏳ **{}**: {} left", title, format_length(length)),
+        )
+        .await
+    {
+        Ok(message) => Some(message),
+        Err(why) => {
+            print_error(&format!("Could not post timer: {:?}", why));
+            None
+        }
+    };
+
+    let mut remaining = length;
+
+    while remaining > 0 {
+        let next = (remaining - 1) / TICK * TICK;
+
+        tokio::time::sleep(Duration::from_secs(remaining - next)).await;
+
+        remaining = next;
+
+        // Stopped, so say so instead of leaving the old time up
+        if !is_running(guild_id, running).await {
+            if let Some(mut message) = countdown {
+                let content = format!("⏹ **{}** was stopped", title);
+
+                if let Err(why) = message.edit(&ctx.http, |m| m.content(content)).await {
+                    print_error(&format!("Could not update timer: {:?}", why));
+                }
+            }
+            return;
+        }
+
+        let content = if remaining == 0 {
+            format!("⌛ **{}** is over!", title)
+        } else {
+            format!("⏳ **{}**: {} left", title, format_length(remaining))
+        };
+
+        if let Some(mut message) = countdown.clone() {
+            if let Err(why) = message.edit(&ctx.http, |m| m.content(content)).await {
+                print_error(&format!("Could not update timer: {:?}", why));
+            }
+        }
+
+        if remaining == WARNING {
+            send_msg(&source, &ctx, format!("**{}**: 1 minute left", title)).await;
+        }
+    }
+
+    print_info(&format!("{} timer finished", title));
+
+    let current_state = get_database(&guild_id).await;
+
+    if kind == "nominations" && current_state.timers.auto_night {
+        send_msg(
+            &source,
+            &ctx,
+            format!("**{}** is over, so it's night!", title),
+        )
+        .await;
+
+        night(&ctx, &source).await;
+    } else {
+        send_msg(&source, &ctx, format!("**{}** is over!", title)).await;
+    }
+}
+
+/// Stops whichever timer is running, so its countdown goes no further
+pub fn stop_timer(current_state: &mut BloodGuild) {
+    current_state.timers.running = NEXT_TIMER.fetch_add(1, Ordering::Relaxed);
+}
+
+pub async fn set_timer(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    match params.get(1).copied() {
+        Some("stop") => {
+            stop_timer(&mut current_state);
+
+            send_msg(msg, ctx, String::from("Stopped the timer")).await;
+        }
+        Some("auto") => {
+            match params.get(2).copied() {
+                Some("on") => current_state.timers.auto_night = true,
+                Some("off") => current_state.timers.auto_night = false,
+                _ => {
+                    send_msg(msg, ctx, String::from("Usage: \"timer auto on/off\"")).await;
+                    return;
+                }
+            }
+
+            send_msg(
+                msg,
+                ctx,
+                format!(
+                    "It **{}** become night when the nominations timer runs out",
+                    if current_state.timers.auto_night {
+                        "will"
+                    } else {
+                        "won't"
+                    }
+                ),
+            )
+            .await;
+        }
+        Some("set") => {
+            let kind = params.get(2).copied().unwrap_or("");
+            let length = params.get(3).and_then(|l| parse_length(l));
+
+            match length {
+                Some(value) if current_state.timers.set_length(kind, value) => {
+                    send_msg(
+                        msg,
+                        ctx,
+                        format!(
+                            "**{}** now lasts {}",
+                            get_timer_title(kind),
+                            format_length(value)
+                        ),
+                    )
+                    .await;
+                }
+                _ => {
+                    send_msg(
+                        msg,
+                        ctx,
+                        String::from(
+                            "Usage: \"timer set discussion/nominations/speech [minutes]\"",
+                        ),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        }
+        Some(kind) if current_state.timers.get_length(kind).is_some() => {
+            if !matches!(current_state.time, Time::Day) {
+                send_msg(
+                    msg,
+                    ctx,
+                    String::from("**Error:** Timers only run during the day!"),
+                )
+                .await;
+                return;
+            }
+
+            // A length given here is just for this timer
+            let length = match params.get(2) {
+                Some(value) => match parse_length(value) {
+                    Some(length) => length,
+                    None => {
+                        send_msg(
+                            msg,
+                            ctx,
+                            String::from("Please give a length like \"5\", \"1:30\" or \"90s\"!"),
+                        )
+                        .await;
+                        return;
+                    }
+                },
+                None => current_state.timers.get_length(kind).unwrap_or_default(),
+            };
+
+            stop_timer(&mut current_state);
+
+            let running = current_state.timers.running;
+
+            let source = TimerSource {
+                guild_id: GuildId(*guild_id),
+                channel_id: ChannelId(current_state.storyteller_channel),
+                author: msg.author().clone(),
+            };
+
+            // Saved first, so the new timer doesn't think it's already been stopped
+            set_database(current_state).await;
+
+            send_msg(
+                msg,
+                ctx,
+                format!(
+                    "Started **{}** for {}",
+                    get_timer_title(kind),
+                    format_length(length)
+                ),
+            )
+            .await;
+
+            tokio::spawn(run_timer(
+                ctx.clone(),
+                source,
+                kind.to_string(),
+                length,
+                running,
+            ));
+            return;
+        }
+        _ => {
+            let timers = &current_state.timers;
+
+            send_msg(
+                msg,
+                ctx,
+                format!(
+                    "**Open discussion:** {}\n**Nominations:** {}\n**Speech:** {}\n**Night when nominations end:** {}\nUsage: \"timer discussion/nominations/speech [length]\", \"timer stop\", \"timer set [timer] [length]\" or \"timer auto on/off\"",
+                    format_length(timers.discussion),
+                    format_length(timers.nominations),
+                    format_length(timers.speech),
+                    if timers.auto_night { "on" } else { "off" }
+                ),
+            )
+            .await;
+            return;
+        }
+    }
+
+    set_database(current_state).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_minutes() {
+        assert_eq!(parse_length("5"), Some(300));
+    }
+
+    #[test]
+    fn parses_minutes_and_seconds() {
+        assert_eq!(parse_length("1:30"), Some(90));
+    }
+
+    #[test]
+    fn parses_seconds() {
+        assert_eq!(parse_length("90s"), Some(90));
+    }
+
+    #[test]
+    fn rejects_bad_lengths() {
+        assert_eq!(parse_length("0"), None);
+        assert_eq!(parse_length("0s"), None);
+        assert_eq!(parse_length("abc"), None);
+        assert_eq!(parse_length(""), None);
+        assert_eq!(parse_length("1:60"), None);
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert_eq!(parse_length("307445734561825861"), None);
+        assert_eq!(parse_length("307445734561825860:59"), None);
+        assert_eq!(parse_length("18446744073709551615"), None);
+    }
+}
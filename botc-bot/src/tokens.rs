@@ -0,0 +1,290 @@
+use crate::*;
+
+// Reminder tokens, like the little discs on a physical grimoire. Each one comes
+// from the reminder list of a character in play, and sits next to a player
+
+#[derive(Clone, PartialEq)]
+pub struct ReminderToken {
+    pub character: String,
+    pub text: String,
+}
+
+impl ReminderToken {
+    pub fn get_string(&self) -> String {
+        format!("{} ({})", self.text, self.character)
+    }
+}
+
+// Tokens that only matter for the night they're placed are marked in the
+// script's Reminders column, so they're taken off at "day"
+fn is_night_token(current_state: &BloodGuild, token: &ReminderToken) -> bool {
+    let character = current_state
+        .game_type
+        .get_character(&token.character)
+        .or_else(|| {
+            current_state
+                .fabled
+                .iter()
+                .find(|f| f.name == token.character)
+                .cloned()
+        });
+
+    match character {
+        Some(value) => value.night_reminders.contains(&token.text),
+        None => false,
+    }
+}
+
+/// Takes off every token that only lasts the night, returning how many there were
+pub fn expire_night_tokens(current_state: &mut BloodGuild) -> usize {
+    let mut expired = 0;
+
+    let mut tokens_left = std::mem::take(&mut current_state.tokens);
+
+    for tokens in tokens_left.values_mut() {
+        let before = tokens.len();
+
+        tokens.retain(|t| !is_night_token(current_state, t));
+
+        expired += before - tokens.len();
+    }
+
+    current_state.tokens = tokens_left;
+
+    current_state.tokens.retain(|_, tokens| !tokens.is_empty());
+
+    expired
+}
+
+// Every token that could be placed right now, from the characters and Fabled in play
+fn get_available_tokens(current_state: &BloodGuild) -> Vec<ReminderToken> {
+    let mut available: Vec<ReminderToken> = Vec::new();
+
+    let characters = current_state
+        .roles
+        .iter()
+        .filter_map(|p| p.2.as_ref())
+        .chain(current_state.fabled.iter());
+
+    for character in characters {
+        for text in &character.reminders {
+            let token = ReminderToken {
+                character: character.name.clone(),
+                text: text.clone(),
+            };
+
+            if !available.contains(&token) {
+                available.push(token);
+            }
+        }
+    }
+
+    available
+}
+
+// Finds the token the storyteller meant, like "poisoned" or "drunk (sailor)"
+fn find_token(available: &[ReminderToken], input: &str) -> Result<ReminderToken, String> {
+    let input = input.to_lowercase();
+
+    // Nothing typed would match every token
+    if input.trim().is_empty() {
+        return Err(String::from("Please give a token, like \"poisoned\"!"));
+    }
+
+    let (text, character) = match input.split_once('(') {
+        Some((text, character)) => (
+            text.trim().to_string(),
+            Some(character.trim_end_matches(')').trim().to_string()),
+        ),
+        None => (input.trim().to_string(), None),
+    };
+
+    let matches_character = |token: &ReminderToken| match &character {
+        Some(value) => token.character.to_lowercase().starts_with(value.as_str()),
+        None => true,
+    };
+
+    let exact: Vec<&ReminderToken> = available
+        .iter()
+        .filter(|t| t.text.to_lowercase() == text && matches_character(t))
+        .collect();
+
+    let matched: Vec<&ReminderToken> = if exact.is_empty() {
+        available
+            .iter()
+            .filter(|t| t.text.to_lowercase().starts_with(&text) && matches_character(t))
+            .collect()
+    } else {
+        exact
+    };
+
+    // The same token twice is still just that token
+    let mut found: Vec<&ReminderToken> = Vec::new();
+
+    for token in matched {
+        if !found.contains(&token) {
+            found.push(token);
+        }
+    }
+
+    match found.len() {
+        0 => Err(format!(
+            "Could not find that token! Tokens in play: {}",
+            available
+                .iter()
+                .map(|t| t.get_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+        1 => Ok(found[0].clone()),
+        _ => Err(format!(
+            "Did you mean {}? Add the character in brackets, like \"drunk (sailor)\"!",
+            found
+                .iter()
+                .map(|t| t.get_string())
+                .collect::<Vec<String>>()
+                .join(" or ")
+        )),
+    }
+}
+
+/// All of a player's tokens on one line, or nothing if they have none
+pub fn get_tokens_str(current_state: &BloodGuild, user_id: u64) -> Option<String> {
+    let tokens = current_state.tokens.get(&user_id)?;
+
+    if tokens.is_empty() {
+        return None;
+    }
+
+    Some(
+        tokens
+            .iter()
+            .map(|t| t.get_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+    )
+}
+
+pub async fn set_token(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let mut current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    let action = params.get(1).copied().unwrap_or("");
+
+    if action != "add" && action != "remove" {
+        let mut content = String::new();
+
+        for (index, player) in current_state.roles.iter().enumerate() {
+            if let Some(tokens) = get_tokens_str(&current_state, player.0) {
+                content.push_str(&format!(
+                    "{}) **{}**: {}\n",
+                    index + 1,
+                    get_member_name(&player.1),
+                    tokens
+                ));
+            }
+        }
+
+        if content.is_empty() {
+            content = String::from("No tokens have been placed.");
+        }
+
+        send_embed(msg, ctx, String::from("Reminder Tokens"), content, None).await;
+        return;
+    }
+
+    let seat = match params.get(2).and_then(|p| current_state.find_seat(p)) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from("Please give a seat and a token, like \"token add 3 poisoned\"!"),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let user_id = current_state.roles[seat].0;
+    let name = get_member_name(&current_state.roles[seat].1);
+    let input = params.get(3..).map(|t| t.join(" ")).unwrap_or_default();
+
+    if action == "add" {
+        let token = match find_token(&get_available_tokens(&current_state), &input) {
+            Ok(value) => value,
+            Err(why) => {
+                send_msg(msg, ctx, why).await;
+                return;
+            }
+        };
+
+        let placed = current_state.tokens.get(&user_id);
+
+        if placed.is_some_and(|tokens| tokens.contains(&token)) {
+            send_msg(
+                msg,
+                ctx,
+                format!("*{}* already has **{}**!", name, token.get_string()),
+            )
+            .await;
+            return;
+        }
+
+        print_info(&format!("Token {} placed on {}", token.get_string(), name));
+        send_msg(
+            msg,
+            ctx,
+            format!("Placed **{}** on *{}*", token.get_string(), name),
+        )
+        .await;
+
+        current_state.tokens.entry(user_id).or_default().push(token);
+    } else {
+        let placed = current_state
+            .tokens
+            .get(&user_id)
+            .cloned()
+            .unwrap_or_default();
+
+        let token = match find_token(&placed, &input) {
+            Ok(value) => value,
+            Err(_) => {
+                send_msg(
+                    msg,
+                    ctx,
+                    format!("*{}* doesn't have a token like that!", name),
+                )
+                .await;
+                return;
+            }
+        };
+
+        if let Some(tokens) = current_state.tokens.get_mut(&user_id) {
+            if let Some(index) = tokens.iter().position(|t| *t == token) {
+                tokens.remove(index);
+            }
+        }
+
+        current_state.tokens.retain(|_, tokens| !tokens.is_empty());
+
+        print_info(&format!(
+            "Token {} removed from {}",
+            token.get_string(),
+            name
+        ));
+        send_msg(
+            msg,
+            ctx,
+            format!("Removed **{}** from *{}*", token.get_string(), name),
+        )
+        .await;
+    }
+
+    set_database(current_state).await;
+}
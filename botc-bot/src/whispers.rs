@@ -0,0 +1,472 @@
+use crate::*;
+
+use serenity::model::interactions::message_component::ButtonStyle;
+
+// Whispers are private conversations between two players during the day. Each
+// one gets its own voice channel only the pair can join, and everyone is told
+// who is whispering, as the rules expect. A player asking for one has to wait
+// for the other to accept. The channels are deleted at night
+
+#[derive(Clone)]
+pub struct Whisper {
+    pub channel: u64,
+    pub first: u64,
+    pub second: u64,
+}
+
+// Whisper rooms go next to the town square, if it's been found yet
+async fn get_whisper_category(ctx: &Context, current_state: &BloodGuild) -> Option<ChannelId> {
+    let town = current_state.town_channel?;
+
+    match ChannelId(town).to_channel(&ctx.http).await {
+        Ok(Channel::Guild(channel)) => channel.category_id,
+        _ => None,
+    }
+}
+
+// Why two seats can't whisper right now, if they can't
+fn check_whisper(current_state: &BloodGuild, first: usize, second: usize) -> Result<(), String> {
+    if !matches!(current_state.time, Time::Day) {
+        return Err(String::from("Whispers can only happen during the day!"));
+    }
+
+    if first == second {
+        return Err(String::from("Nobody can whisper with themselves!"));
+    }
+
+    let first_id = current_state.roles[first].0;
+    let second_id = current_state.roles[second].0;
+
+    let busy = current_state.whispers.iter().any(|w| {
+        [w.first, w.second].contains(&first_id) || [w.first, w.second].contains(&second_id)
+    });
+
+    if busy {
+        return Err(format!(
+            "*{}* or *{}* is already whispering!",
+            get_member_name(&current_state.roles[first].1),
+            get_member_name(&current_state.roles[second].1)
+        ));
+    }
+
+    Ok(())
+}
+
+// Adds the whisper to the saved game, unless it stopped being allowed while
+// its channel was made, like night falling or one of them whispering already
+async fn save_whisper(guild_id: u64, whisper: Whisper) -> Result<(), String> {
+    let mut lock = BLOOD_DATABASE.lock().await;
+
+    let value = match lock.blood_guilds.get_mut(&guild_id) {
+        Some(value) => value,
+        None => return Err(String::from("That game has ended!")),
+    };
+
+    let seats = (
+        value.roles.iter().position(|p| p.0 == whisper.first),
+        value.roles.iter().position(|p| p.0 == whisper.second),
+    );
+
+    match seats {
+        (Some(first), Some(second)) => check_whisper(value, first, second)?,
+        _ => return Err(String::from("They're no longer in the game!")),
+    }
+
+    value.whispers.push(whisper);
+
+    Ok(())
+}
+
+/// Opens a whisper between two seats, returning the public announcement or why it couldn't.
+/// The whisper is saved straight into the game, so the caller doesn't save anything
+async fn start_whisper(
+    ctx: &Context,
+    current_state: &BloodGuild,
+    first: usize,
+    second: usize,
+) -> Result<String, String> {
+    check_whisper(current_state, first, second)?;
+
+    let (first_id, first_member, _) = current_state.roles[first].clone();
+    let (second_id, second_member, _) = current_state.roles[second].clone();
+
+    let names = (
+        get_member_name(&first_member),
+        get_member_name(&second_member),
+    );
+
+    let category = get_whisper_category(ctx, current_state).await;
+
+    // Hidden from everyone but the pair
+    let overwrites = vec![
+        PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::READ_MESSAGES | Permissions::CONNECT,
+            kind: PermissionOverwriteType::Role(RoleId(current_state.id)),
+        },
+        PermissionOverwrite {
+            allow: Permissions::READ_MESSAGES | Permissions::CONNECT | Permissions::SPEAK,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(UserId(first_id)),
+        },
+        PermissionOverwrite {
+            allow: Permissions::READ_MESSAGES | Permissions::CONNECT | Permissions::SPEAK,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(UserId(second_id)),
+        },
+    ];
+
+    let channel = GuildId(current_state.id)
+        .create_channel(&ctx.http, |c| {
+            c.name(format!("Whisper: {} & {}", names.0, names.1))
+                .kind(ChannelType::Voice)
+                .permissions(overwrites);
+
+            if let Some(value) = category {
+                c.category(value);
+            }
+
+            c
+        })
+        .await
+        .map_err(|why| {
+            print_error(&format!("Could not create whisper channel: {:?}", why));
+            String::from("Could not create a whisper channel! Does the bot have *Manage Channels*?")
+        })?;
+
+    // Saved before anyone is moved, so the watchdog knows where they're going
+    let whisper = Whisper {
+        channel: *channel.id.as_u64(),
+        first: first_id,
+        second: second_id,
+    };
+
+    if let Err(why) = save_whisper(current_state.id, whisper).await {
+        if let Err(why) = channel.delete(&ctx.http).await {
+            print_error(&format!("Could not delete whisper channel: {:?}", why));
+        }
+
+        return Err(why);
+    }
+
+    // Anyone already in voice is taken straight there, and anyone else can join
+    let moves = vec![
+        MoveRequest {
+            user_id: UserId(first_id),
+            name: names.0.clone(),
+            channel_id: channel.id,
+        },
+        MoveRequest {
+            user_id: UserId(second_id),
+            name: names.1.clone(),
+            channel_id: channel.id,
+        },
+    ];
+
+    move_members(&ctx.http, GuildId(current_state.id), moves).await;
+
+    print_info(&format!("{} and {} are whispering", names.0, names.1));
+
+    Ok(format!(
+        "🤫 <@{}> and <@{}> are whispering",
+        first_id, second_id
+    ))
+}
+
+// Whispers are public, so they're announced where everyone can see
+async fn announce_whisper(ctx: &Context, current_state: &BloodGuild, content: &str) {
+    let channel = current_state
+        .public_channel
+        .unwrap_or(current_state.storyteller_channel);
+
+    if let Err(why) = ChannelId(channel).say(&ctx.http, content).await {
+        print_error(&format!("Could not announce whisper: {:?}", why));
+    }
+}
+
+async fn delete_whispers(ctx: &Context, whispers: Vec<Whisper>) -> usize {
+    let count = whispers.len();
+
+    for whisper in whispers {
+        if let Err(why) = ChannelId(whisper.channel).delete(&ctx.http).await {
+            print_error(&format!("Could not delete whisper channel: {:?}", why));
+        }
+    }
+
+    count
+}
+
+/// Deletes every whisper channel, returning how many there were
+pub async fn end_whispers(ctx: &Context, current_state: &mut BloodGuild) -> usize {
+    delete_whispers(ctx, std::mem::take(&mut current_state.whispers)).await
+}
+
+/// Same as end_whispers, but on the saved game, for after it's been saved
+pub async fn end_game_whispers(ctx: &Context, guild_id: u64) -> usize {
+    let whispers = match BLOOD_DATABASE.lock().await.blood_guilds.get_mut(&guild_id) {
+        Some(value) => std::mem::take(&mut value.whispers),
+        None => return 0,
+    };
+
+    delete_whispers(ctx, whispers).await
+}
+
+pub async fn set_whisper(ctx: &Context, msg: &impl CommandSource) {
+    print_command(msg);
+
+    let guild_id = &msg.guild_id().unwrap().0;
+
+    let current_state = get_database(guild_id).await;
+
+    let params: Vec<&str> = msg.content().split(' ').collect();
+
+    if params.get(1) == Some(&"end") {
+        let count = end_game_whispers(ctx, *guild_id).await;
+
+        send_msg(msg, ctx, format!("Ended {} whispers", count)).await;
+        return;
+    }
+
+    let seats = (
+        params.get(1).and_then(|p| current_state.find_seat(p)),
+        params.get(2).and_then(|p| current_state.find_seat(p)),
+    );
+
+    let (first, second) = match seats {
+        (Some(first), Some(second)) => (first, second),
+        _ => {
+            let mut content = String::from("**Whispers:**\n");
+
+            if current_state.whispers.is_empty() {
+                content.push_str("None\n");
+            }
+
+            for whisper in &current_state.whispers {
+                content.push_str(&format!(
+                    "<@{}> and <@{}> in <#{}>\n",
+                    whisper.first, whisper.second, whisper.channel
+                ));
+            }
+
+            content.push_str("Usage: \"whisper 3 7\" or \"whisper end\"");
+
+            send_msg(msg, ctx, content).await;
+            return;
+        }
+    };
+
+    match start_whisper(ctx, &current_state, first, second).await {
+        Ok(content) => {
+            announce_whisper(ctx, &current_state, &content).await;
+
+            send_msg(msg, ctx, String::from("Whisper started")).await;
+        }
+        Err(why) => send_msg(msg, ctx, format!("**Error:** {}", why)).await,
+    }
+}
+
+// Players can pick who to whisper with by seat, mention or part of their name
+fn find_player(current_state: &BloodGuild, input: &str) -> Option<usize> {
+    if input.is_empty() {
+        return None;
+    }
+
+    if let Some(seat) = current_state.find_seat(input) {
+        return Some(seat);
+    }
+
+    let input = input.to_lowercase();
+
+    let found: Vec<usize> = (0..current_state.roles.len())
+        .filter(|seat| {
+            get_member_name(&current_state.roles[*seat].1)
+                .to_lowercase()
+                .contains(&input)
+        })
+        .collect();
+
+    match found.len() {
+        1 => Some(found[0]),
+        _ => None,
+    }
+}
+
+/// "whisper [player]" sent to the bot in a DM, which asks the other player first
+pub async fn request_whisper(ctx: &Context, msg: &Message, guild_id: u64, text: &str) {
+    let current_state = get_database(&guild_id).await;
+
+    let user_id = *msg.author.id.as_u64();
+
+    let first = match current_state.roles.iter().position(|p| p.0 == user_id) {
+        Some(value) => value,
+        None => return,
+    };
+
+    let second = match find_player(&current_state, text.trim()) {
+        Some(value) => value,
+        None => {
+            send_msg(
+                msg,
+                ctx,
+                String::from(
+                    "Who with? Send \"whisper\" and their seat number or name, like \"whisper 3\"",
+                ),
+            )
+            .await;
+            return;
+        }
+    };
+
+    if let Err(why) = check_whisper(&current_state, first, second) {
+        send_msg(msg, ctx, why).await;
+        return;
+    }
+
+    let name = get_member_name(&current_state.roles[first].1);
+    let (_, other, _) = &current_state.roles[second];
+
+    // The day is in the ID, so yesterday's request can't be accepted today
+    let custom_id = format!(
+        "whisper_{}_{}_{}",
+        guild_id, current_state.day_index, user_id
+    );
+
+    let result = match other.user.create_dm_channel(&ctx.http).await {
+        Ok(channel) => {
+            channel
+                .send_message(&ctx.http, |m| {
+                    m.content(format!("*{}* would like to whisper with you.", name))
+                        .components(|c| {
+                            c.create_action_row(|row| {
+                                row.create_button(|b| {
+                                    b.custom_id(format!("{}_accept", custom_id))
+                                        .label("Accept")
+                                        .style(ButtonStyle::Primary)
+                                })
+                                .create_button(|b| {
+                                    b.custom_id(format!("{}_decline", custom_id))
+                                        .label("Decline")
+                                        .style(ButtonStyle::Secondary)
+                                })
+                            })
+                        })
+                })
+                .await
+        }
+        Err(why) => Err(why),
+    };
+
+    let content = match result {
+        Ok(_) => format!(
+            "Asked *{}* to whisper, you'll hear back when they answer.",
+            get_member_name(other)
+        ),
+        Err(why) => {
+            print_error(&format!("Could not send whisper request: {:?}", why));
+            format!("Could not message *{}*!", get_member_name(other))
+        }
+    };
+
+    send_msg(msg, ctx, content).await;
+}
+
+// Lets the player who asked for a whisper know how it went
+async fn tell_requester(ctx: &Context, member: &Member, content: String) {
+    if let Err(why) = member
+        .user
+        .direct_message(&ctx.http, |m| m.content(content))
+        .await
+    {
+        print_error(&format!(
+            "Could not send message to {}: {:?}",
+            member.user.name, why
+        ));
+    }
+}
+
+/// Accept or Decline pressed on a whisper request
+pub async fn handle_whisper_press(ctx: &Context, press: ComponentPress) {
+    // Like "whisper_[guild]_[day]_[requester]_[accept/decline]"
+    let parts: Vec<&str> = press.content.split('_').collect();
+
+    let (guild_id, day, requester) = match (
+        parts.get(1).and_then(|p| p.parse::<u64>().ok()),
+        parts.get(2).and_then(|p| p.parse::<u32>().ok()),
+        parts.get(3).and_then(|p| p.parse::<u64>().ok()),
+    ) {
+        (Some(guild_id), Some(day), Some(requester)) => (guild_id, day, requester),
+        _ => return,
+    };
+
+    let current_state = match BLOOD_DATABASE.lock().await.blood_guilds.get(&guild_id) {
+        Some(value) => value.clone(),
+        None => {
+            send_msg(&press, ctx, String::from("That game has ended!")).await;
+            return;
+        }
+    };
+
+    if current_state.day_index != day || !matches!(current_state.time, Time::Day) {
+        send_msg(
+            &press,
+            ctx,
+            String::from("That request is from an earlier day!"),
+        )
+        .await;
+        return;
+    }
+
+    let user_id = *press.interaction.user.id.as_u64();
+
+    let seats = (
+        current_state.roles.iter().position(|p| p.0 == requester),
+        current_state.roles.iter().position(|p| p.0 == user_id),
+    );
+
+    let (first, second) = match seats {
+        (Some(first), Some(second)) => (first, second),
+        _ => {
+            send_msg(&press, ctx, String::from("They're no longer in the game!")).await;
+            return;
+        }
+    };
+
+    let requester_member = current_state.roles[first].1.clone();
+    let name = get_member_name(&current_state.roles[second].1);
+
+    if parts.get(4) != Some(&"accept") {
+        send_msg(&press, ctx, String::from("You turned down the whisper.")).await;
+
+        tell_requester(
+            ctx,
+            &requester_member,
+            format!("*{}* doesn't want to whisper right now.", name),
+        )
+        .await;
+        return;
+    }
+
+    match start_whisper(ctx, &current_state, first, second).await {
+        Ok(content) => {
+            announce_whisper(ctx, &current_state, &content).await;
+
+            send_msg(
+                &press,
+                ctx,
+                String::from("Your whisper channel is ready, it closes at night."),
+            )
+            .await;
+
+            tell_requester(
+                ctx,
+                &requester_member,
+                format!(
+                    "*{}* accepted, your whisper channel is ready. It closes at night.",
+                    name
+                ),
+            )
+            .await;
+        }
+        Err(why) => send_msg(&press, ctx, why).await,
+    }
+}